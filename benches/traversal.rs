@@ -0,0 +1,254 @@
+//! Traversal benchmarks over a generated tree.
+//!
+//! Run with `cargo bench --features stats`. The tree mimics a folder of
+//! projects: sources, build output and dependencies, a few levels deep. Each
+//! benchmark prints the median of its runs.
+//!
+//! - `scan/baseline` is the scan of neaten before [`Walker`], as of the first
+//!   commit (`helper::remove` in dry-run, without its messages): children are
+//!   bare paths, so matching a folder pattern asks the type of each one
+//!   (`Path::is_dir`, a `stat`) and descending into it asks again.
+//! - `scan/neaten` is the scan of [`Scanner::scan`], on one thread and on
+//!   all of them. Both scans print their number of `stat` calls, neaten's as
+//!   counted by `neaten::stats` of the `stats` feature.
+//! - `neaten/dryrun` runs the binary on the tree without cache.
+//! - `neaten/cached` runs it again with the cache of a previous run, so
+//!   unchanged directories are not read.
+
+use neaten::{Config, Kind, Scanner};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::atomic::{AtomicU64, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+const PROJECTS: usize = 200;
+const RUNS: usize = 10;
+/// folders matched by the scans, which aren't searched.
+const PATTERNS: [&str; 2] = ["target", "node_modules"];
+
+fn main() {
+    let dir = tempfile::tempdir().unwrap();
+    let tree = dir.path().join("tree");
+    let entries = generate(&tree);
+    println!("tree: {} entries in {:?}", entries, tree);
+
+    // same items, whatever the scan
+    let configs = vec![Config::new(&tree, Kind::Folder, PATTERNS.to_vec(), None)];
+    let scan = || {
+        let (plan, _) = Scanner::new(&configs).force(true).scan();
+        let mut items: Vec<PathBuf> = plan.items().map(|item| item.path.clone()).collect();
+        items.sort();
+        items
+    };
+    let mut baseline = baseline::scan(&tree);
+    baseline.sort();
+    assert_eq!(baseline, scan());
+    assert_eq!(baseline.len(), PROJECTS * PATTERNS.len());
+
+    let before = baseline::STATS.load(Ordering::Relaxed);
+    bench("scan/baseline", || {
+        baseline::scan(&tree);
+    });
+    stats(
+        "scan/baseline",
+        baseline::STATS.load(Ordering::Relaxed) - before,
+    );
+
+    let single = rayon::ThreadPoolBuilder::new()
+        .num_threads(1)
+        .build()
+        .unwrap();
+    #[cfg(feature = "stats")]
+    let before = neaten::stats();
+    bench("scan/neaten-1", || {
+        single.install(scan);
+    });
+    #[cfg(feature = "stats")]
+    stats("scan/neaten-1", neaten::stats() - before);
+    bench("scan/neaten", || {
+        scan();
+    });
+    #[cfg(not(feature = "stats"))]
+    println!("scan/neaten      stat calls are counted with --features stats");
+
+    let state = dir.path().join("state");
+    let run = |cache: bool| {
+        let mut command = neaten(&tree, &state);
+        if !cache {
+            command.arg("--no-cache");
+        }
+        command
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .unwrap();
+    };
+    bench("neaten/dryrun", || run(false));
+
+    // directories modified within the last seconds are not cached
+    thread::sleep(Duration::from_secs(3));
+    bench("neaten/cached", || run(true));
+}
+
+/// Generate the tree, return its number of entries.
+fn generate(root: &Path) -> usize {
+    let mut entries = 0;
+    for project in 0..PROJECTS {
+        let project = root.join(format!("project{:03}", project));
+        for dir in ["src/app/model", "src/app/view", "tests", "docs"] {
+            let dir = project.join(dir);
+            fs::create_dir_all(&dir).unwrap();
+            for file in 0..5 {
+                fs::write(dir.join(format!("file{}.rs", file)), "fn main() {}").unwrap();
+                entries += 1;
+            }
+        }
+        for dir in ["target/debug/deps", "node_modules/pkg/lib"] {
+            let dir = project.join(dir);
+            fs::create_dir_all(&dir).unwrap();
+            for file in 0..10 {
+                fs::write(dir.join(format!("file{}.o", file)), "object").unwrap();
+                entries += 1;
+            }
+        }
+        entries += 14;
+    }
+    entries
+}
+
+/// Print the number of `stat` calls of a scan, out of `total` for its runs.
+fn stats(name: &str, total: u64) {
+    // scanned once more to warm up
+    println!("{:<16} {:>10} stat calls", name, total / (RUNS as u64 + 1));
+}
+
+/// Scan of neaten before [`Walker`](neaten::Walker), as of the first commit:
+/// `helper::remove` in dry-run, whose messages are replaced by the list of
+/// matched items, and whose `stat` calls are counted.
+mod baseline {
+    use super::*;
+
+    pub static STATS: AtomicU64 = AtomicU64::new(0);
+
+    /// Matched items of `destination`.
+    pub fn scan(destination: &Path) -> Vec<PathBuf> {
+        let patterns: Vec<String> = PATTERNS.iter().map(|pattern| pattern.to_string()).collect();
+        let mut matched = vec![];
+        self::remove(destination, &Kind::Folder, &patterns, &[], &mut matched);
+        matched
+    }
+
+    fn remove(
+        destination: &Path,
+        kind: &Kind,
+        patterns: &[String],
+        exclude: &[String],
+        matched: &mut Vec<PathBuf>,
+    ) {
+        if self::stat(Path::exists, destination) {
+            // get child item of kind
+            let children = self::childern(destination, exclude);
+
+            // iterate over each child
+            for child in &children {
+                // if match, then remove
+                match self::pattern_check(child, patterns, kind) {
+                    Some(_) => matched.push(child.clone()),
+                    None => {
+                        if self::stat(Path::is_dir, child) {
+                            self::remove(child, kind, patterns, exclude, matched);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn childern(parent: &Path, exclude: &[String]) -> Vec<PathBuf> {
+        let mut children = Vec::new();
+        if let Ok(entries) = fs::read_dir(parent) {
+            for entry in entries.flatten() {
+                // don't add path that exists in exclude list
+                let path = entry.path();
+                let name = path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_str()
+                    .unwrap_or_default();
+                if self::find(name, exclude).is_none() {
+                    children.push(path);
+                }
+            }
+        }
+        children
+    }
+
+    fn find(item: &str, list: &[String]) -> Option<usize> {
+        list.iter()
+            .position(|n| n.to_lowercase() == item.to_lowercase())
+    }
+
+    fn pattern_check(path: &Path, patterns: &[String], kind: &Kind) -> Option<usize> {
+        // check for folder
+        if *kind == Kind::Folder && self::stat(Path::is_dir, path) {
+            let name = path
+                .file_name()
+                .unwrap_or_default()
+                .to_str()
+                .unwrap_or_default();
+            self::find(name, patterns)
+        } else if *kind == Kind::File && self::stat(Path::is_file, path) {
+            let extn = path
+                .extension()
+                .unwrap_or_default()
+                .to_str()
+                .unwrap_or_default();
+            self::find(extn, patterns)
+        } else {
+            None
+        }
+    }
+
+    /// `check` of `path`, a `stat` call.
+    fn stat(check: fn(&Path) -> bool, path: &Path) -> bool {
+        STATS.fetch_add(1, Ordering::Relaxed);
+        check(path)
+    }
+}
+
+/// Command running neaten in dry-run on `tree`, never matching anything so
+/// the whole tree is searched.
+fn neaten(tree: &Path, state: &Path) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_neaten"));
+    command
+        .env("XDG_STATE_HOME", state)
+        .env("XDG_CACHE_HOME", state)
+        .args(["--dryrun", "--quiet", "--jobs", "1", "-k", "folder"])
+        .args(["-p", "nothing-matches", "-d"])
+        .arg(tree);
+    command
+}
+
+fn bench<F: FnMut()>(name: &str, mut f: F) {
+    // warm up caches
+    f();
+
+    let mut durations: Vec<Duration> = (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .collect();
+    durations.sort();
+    println!(
+        "{:<16} {:>10.2?} (median of {})",
+        name,
+        durations[RUNS / 2],
+        RUNS
+    );
+}
@@ -0,0 +1,314 @@
+//! Disk usage of matched items, without removing anything, to decide which
+//! patterns are worth cleaning.
+
+use crate::{
+    size::{Bytes, Size},
+    stats,
+    theme::{self, Style},
+    walker::Walker,
+};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Reverse,
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Order of matched items.
+#[derive(Deserialize, Default, Debug, PartialEq, Clone, Copy, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Sort {
+    /// biggest first.
+    #[default]
+    Size,
+    /// least recently modified first.
+    Age,
+}
+
+#[derive(Serialize, Default, Debug, PartialEq)]
+pub struct Analysis {
+    /// matched items, sorted.
+    pub artifacts: Vec<Artifact>,
+    /// matched items per pattern, biggest first.
+    pub patterns: Vec<Group>,
+    /// matched items per project (parent directory), biggest first.
+    pub projects: Vec<Group>,
+    pub size: Size,
+}
+
+/// Matched item.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Artifact {
+    pub path: PathBuf,
+    pub pattern: String,
+    pub project: PathBuf,
+    pub size: Size,
+    /// latest modification of the item or its content, seconds since UNIX epoch.
+    pub modified: Option<u64>,
+}
+
+#[derive(Serialize, Default, Debug, PartialEq)]
+pub struct Group {
+    pub name: String,
+    pub items: usize,
+    pub size: Size,
+    /// latest modification of its items, seconds since UNIX epoch.
+    pub modified: Option<u64>,
+}
+
+impl Artifact {
+    pub fn new<P: Into<PathBuf>>(path: P, pattern: &str) -> Artifact {
+        let path = path.into();
+        let (size, modified) = self::measure(&path);
+        let modified = modified
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| duration.as_secs());
+        Artifact::measured(path, pattern, size, modified)
+    }
+
+    /// Item whose size and latest modification are already known.
+    pub fn measured<P: Into<PathBuf>>(
+        path: P,
+        pattern: &str,
+        size: Size,
+        modified: Option<u64>,
+    ) -> Artifact {
+        let path = path.into();
+        Artifact {
+            project: path.parent().map(Path::to_path_buf).unwrap_or_default(),
+            pattern: pattern.to_lowercase(),
+            size,
+            modified,
+            path,
+        }
+    }
+}
+
+impl Analysis {
+    pub fn new(mut artifacts: Vec<Artifact>, sort: Sort) -> Analysis {
+        match sort {
+            Sort::Size => artifacts.sort_by_key(|artifact| Reverse(artifact.size.disk)),
+            Sort::Age => artifacts.sort_by_key(|artifact| artifact.modified),
+        }
+
+        Analysis {
+            patterns: self::group(&artifacts, |artifact| artifact.pattern.clone()),
+            projects: self::group(&artifacts, |artifact| {
+                artifact.project.display().to_string()
+            }),
+            size: artifacts
+                .iter()
+                .fold(Size::default(), |size, artifact| size + artifact.size),
+            artifacts,
+        }
+    }
+
+    /// Tables of the analysis, listing `top` items and projects at most.
+    pub fn table(&self, top: usize, now: u64) -> String {
+        let age = |modified: Option<u64>| {
+            modified
+                .map(|modified| self::age(now.saturating_sub(modified)))
+                .unwrap_or(String::from("-"))
+        };
+
+        let mut msg = format!("{}\n", theme::paint(Style::Bold, "Largest items"));
+        msg = format!(
+            "{}{:>10}  {:>10}  {:>6}  {:<16}  {}\n",
+            msg, "On disk", "Apparent", "Age", "Pattern", "Path"
+        );
+        for artifact in self.artifacts.iter().take(top) {
+            msg = format!(
+                "{}{:>10}  {:>10}  {:>6}  {:<16}  {}\n",
+                msg,
+                Bytes(artifact.size.disk).to_string(),
+                Bytes(artifact.size.apparent).to_string(),
+                age(artifact.modified),
+                artifact.pattern,
+                artifact.path.display()
+            );
+        }
+
+        for (heading, groups) in [
+            ("By pattern", &self.patterns),
+            ("By project", &self.projects),
+        ] {
+            msg = format!("{}\n{}\n", msg, theme::paint(Style::Bold, heading));
+            msg = format!(
+                "{}{:>10}  {:>6}  {:>6}  {}\n",
+                msg, "On disk", "Items", "Age", "Name"
+            );
+            for group in groups.iter().take(top) {
+                msg = format!(
+                    "{}{:>10}  {:>6}  {:>6}  {}\n",
+                    msg,
+                    Bytes(group.size.disk).to_string(),
+                    group.items,
+                    age(group.modified),
+                    group.name
+                );
+            }
+        }
+
+        format!(
+            "{}\n{} items, {} ({} apparent)",
+            msg,
+            self.artifacts.len(),
+            Bytes(self.size.disk),
+            Bytes(self.size.apparent)
+        )
+    }
+}
+
+fn group<F: Fn(&Artifact) -> String>(artifacts: &[Artifact], key: F) -> Vec<Group> {
+    let mut groups: BTreeMap<String, Group> = BTreeMap::new();
+    for artifact in artifacts {
+        let name = key(artifact);
+        let group = groups.entry(name.clone()).or_insert(Group {
+            name,
+            ..Default::default()
+        });
+        group.items += 1;
+        group.size += artifact.size;
+        group.modified = group.modified.max(artifact.modified);
+    }
+
+    // stable for equal sizes, as groups are ordered by name
+    let mut groups: Vec<Group> = groups.into_values().collect();
+    groups.sort_by_key(|group| Reverse(group.size.disk));
+    groups
+}
+
+/// Size and latest modification of `path` and its content, in a single walk,
+/// symbolic links are not followed.
+fn measure(path: &Path) -> (Size, Option<SystemTime>) {
+    stats::stat();
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return (Size::default(), None);
+    };
+    let mut size = Size::from_metadata(&metadata);
+    let mut newest = metadata.modified().ok();
+    if metadata.is_dir() {
+        let entries = Walker::new(path).flatten();
+        for metadata in entries.filter_map(|entry| entry.metadata().ok()) {
+            size += Size::from_metadata(&metadata);
+            newest = newest.max(metadata.modified().ok());
+        }
+    }
+    (size, newest)
+}
+
+/// Human readable age of `seconds`, e.g. `3d`.
+fn age(seconds: u64) -> String {
+    const UNITS: [(u64, &str); 5] = [
+        (365 * 86400, "y"),
+        (30 * 86400, "mo"),
+        (86400, "d"),
+        (3600, "h"),
+        (60, "m"),
+    ];
+
+    UNITS
+        .iter()
+        .find(|(unit, _)| seconds >= *unit)
+        .map(|(unit, name)| format!("{}{}", seconds / unit, name))
+        .unwrap_or(String::from("now"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn artifact(path: &str, pattern: &str, disk: u64, modified: u64) -> Artifact {
+        let path = PathBuf::from(path);
+        Artifact {
+            project: path.parent().unwrap().to_path_buf(),
+            path,
+            pattern: String::from(pattern),
+            size: Size {
+                apparent: disk,
+                disk,
+            },
+            modified: Some(modified),
+        }
+    }
+
+    fn artifacts() -> Vec<Artifact> {
+        vec![
+            artifact("/pool/a/target", "target", 10, 300),
+            artifact("/pool/a/node_modules", "node_modules", 50, 100),
+            artifact("/pool/b/target", "target", 30, 200),
+        ]
+    }
+
+    #[test]
+    fn sort_and_group() {
+        let analysis = Analysis::new(artifacts(), Sort::Size);
+        let paths: Vec<&str> = analysis
+            .artifacts
+            .iter()
+            .map(|artifact| artifact.path.to_str().unwrap())
+            .collect();
+        assert_eq!(
+            paths,
+            vec!["/pool/a/node_modules", "/pool/b/target", "/pool/a/target"]
+        );
+        assert_eq!(
+            analysis.patterns,
+            vec![
+                Group {
+                    name: String::from("node_modules"),
+                    items: 1,
+                    size: Size {
+                        apparent: 50,
+                        disk: 50
+                    },
+                    modified: Some(100),
+                },
+                Group {
+                    name: String::from("target"),
+                    items: 2,
+                    size: Size {
+                        apparent: 40,
+                        disk: 40
+                    },
+                    modified: Some(300),
+                },
+            ]
+        );
+        assert_eq!(analysis.projects[0].name, "/pool/a");
+        assert_eq!(analysis.projects[0].items, 2);
+        assert_eq!(analysis.size.disk, 90);
+
+        let analysis = Analysis::new(artifacts(), Sort::Age);
+        assert_eq!(
+            analysis.artifacts[0].path,
+            PathBuf::from("/pool/a/node_modules")
+        );
+        assert_eq!(analysis.artifacts[2].path, PathBuf::from("/pool/a/target"));
+    }
+
+    #[test]
+    fn artifact_of_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("proj").join("target");
+        fs::create_dir_all(target.join("debug")).unwrap();
+        fs::write(target.join("debug").join("app"), "binary").unwrap();
+
+        let artifact = Artifact::new(&target, "Target");
+        assert_eq!(artifact.pattern, "target");
+        assert_eq!(artifact.project, dir.path().join("proj"));
+        assert!(artifact.size.apparent >= 6);
+        assert!(artifact.modified.is_some());
+    }
+
+    #[test]
+    fn human_age() {
+        assert_eq!(age(30), "now");
+        assert_eq!(age(90), "1m");
+        assert_eq!(age(3 * 86400 + 10), "3d");
+        assert_eq!(age(400 * 86400), "1y");
+    }
+}
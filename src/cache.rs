@@ -0,0 +1,252 @@
+//! Scan results kept between runs, to skip work on unchanged trees.
+//!
+//! A directory in which a scan found nothing (no match, exclude, skipped item
+//! or error) is recorded with its modification time and its sub-directories.
+//! As long as its modification time is unchanged, its entries are unchanged
+//! too, so the next scan doesn't read it again and only walks its
+//! sub-directories. Sizes of matched files are recorded for `neaten scan`,
+//! those of folders aren't since content deep inside may change without
+//! changing their modification time.
+//!
+//! Records depend on the config entries, each set of entries has its own file
+//! in `$XDG_CACHE_HOME/neaten` (or `~/.cache/neaten`, `%LOCALAPPDATA%\neaten\cache`
+//! on Windows).
+
+use crate::{
+    Config,
+    error::{AppError, AppErrorKind},
+    size::Size,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+
+/// Version of the cache file, files of another version are ignored.
+const VERSION: u32 = 1;
+
+/// Directories modified this close to the scan may still change within the
+/// same timestamp, they are not recorded.
+const SETTLE: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Default)]
+pub struct Cache {
+    /// file records are loaded from and saved to, none when caching is disabled.
+    file: Option<PathBuf>,
+    /// records of the previous scan.
+    previous: Records,
+    /// records of this scan, only these are saved.
+    current: Mutex<Records>,
+    started: Option<SystemTime>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+struct Records {
+    version: u32,
+    listings: HashMap<PathBuf, Listing>,
+    sizes: HashMap<PathBuf, Measure>,
+}
+
+/// Directory in which nothing was found.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Listing {
+    modified: SystemTime,
+    /// names of its sub-directories.
+    dirs: Vec<String>,
+}
+
+/// Size of a matched file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Measure {
+    modified: SystemTime,
+    pub size: Size,
+    /// latest modification of the item or its content, seconds since UNIX epoch.
+    pub newest: Option<u64>,
+}
+
+impl Cache {
+    /// Cache of `configs` in `dir`, empty if it doesn't exist or can't be read.
+    pub fn load<P: AsRef<Path>>(dir: P, configs: &[Config]) -> Cache {
+        let file = dir.as_ref().join(format!("{}.json", self::key(configs)));
+        let previous = fs::read_to_string(&file)
+            .ok()
+            .and_then(|json| serde_json::from_str::<Records>(&json).ok())
+            .filter(|records| records.version == VERSION)
+            .unwrap_or_default();
+
+        Cache {
+            file: Some(file),
+            previous,
+            current: Mutex::new(Records {
+                version: VERSION,
+                ..Default::default()
+            }),
+            started: Some(SystemTime::now()),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.file.is_some()
+    }
+
+    /// Sub-directories of `dir`, if it's recorded and wasn't modified since.
+    pub fn dirs(&self, dir: &Path, modified: SystemTime) -> Option<&[String]> {
+        self.previous
+            .listings
+            .get(dir)
+            .filter(|listing| listing.modified == modified)
+            .map(|listing| listing.dirs.as_slice())
+    }
+
+    /// Record that nothing was found in `dir`.
+    pub fn insert_dirs(&self, dir: PathBuf, modified: SystemTime, dirs: Vec<String>) {
+        if !self.settled(modified) {
+            return;
+        }
+        if let Ok(mut current) = self.current.lock() {
+            current.listings.insert(dir, Listing { modified, dirs });
+        }
+    }
+
+    /// Size of matched file `path`, if it's recorded and wasn't modified since.
+    pub fn size(&self, path: &Path, modified: SystemTime) -> Option<Measure> {
+        self.previous
+            .sizes
+            .get(path)
+            .filter(|measure| measure.modified == modified)
+            .cloned()
+    }
+
+    pub fn insert_size(
+        &self,
+        path: PathBuf,
+        modified: SystemTime,
+        size: Size,
+        newest: Option<u64>,
+    ) {
+        if !self.settled(modified) {
+            return;
+        }
+        if let Ok(mut current) = self.current.lock() {
+            let measure = Measure {
+                modified,
+                size,
+                newest,
+            };
+            current.sizes.insert(path, measure);
+        }
+    }
+
+    /// Save records of this scan, replacing the previous ones.
+    pub fn save(&self) -> crate::Result<()> {
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+        let current = self
+            .current
+            .lock()
+            .map_err(|_| AppError::new(AppErrorKind::Internal, "cache records are poisoned"))?;
+
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // another run may read it meanwhile
+        let temp = file.with_extension(format!("{}.tmp", std::process::id()));
+        fs::write(&temp, serde_json::to_string(&*current)?)?;
+        fs::rename(&temp, file)?;
+        Ok(())
+    }
+
+    fn settled(&self, modified: SystemTime) -> bool {
+        self.file.is_some()
+            && self
+                .started
+                .is_some_and(|started| modified + SETTLE <= started)
+    }
+}
+
+/// Directory of cache files.
+pub fn dir() -> crate::Result<PathBuf> {
+    let cache = env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("LOCALAPPDATA").map(|dir| PathBuf::from(dir).join("cache")))
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .ok_or(AppError::new(
+            AppErrorKind::Functionality,
+            "can't find cache directory, please set XDG_CACHE_HOME",
+        ))?;
+
+    Ok(cache.join("neaten"))
+}
+
+/// Remove every cache file in `dir`, return how many were removed.
+pub fn clear<P: AsRef<Path>>(dir: P) -> crate::Result<usize> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(0);
+    };
+
+    let mut count = 0;
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_some_and(|extn| extn == "json") {
+            fs::remove_file(path)?;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Name of the cache file of `configs`.
+fn key(configs: &[Config]) -> String {
+    format!("{:x}", Sha256::digest(format!("{:?}", configs)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Kind;
+
+    #[test]
+    fn save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let configs = vec![Config::new("/pool", Kind::Folder, vec!["target"], None)];
+        let old = SystemTime::UNIX_EPOCH + Duration::from_secs(1000);
+
+        let cache = Cache::load(dir.path(), &configs);
+        assert_eq!(cache.dirs(Path::new("/pool/a"), old), None);
+        cache.insert_dirs(PathBuf::from("/pool/a"), old, vec![String::from("src")]);
+        // too recent to be trusted
+        cache.insert_dirs(PathBuf::from("/pool/b"), SystemTime::now(), vec![]);
+        cache.save().unwrap();
+
+        let cache = Cache::load(dir.path(), &configs);
+        assert_eq!(
+            cache.dirs(Path::new("/pool/a"), old),
+            Some(&[String::from("src")][..])
+        );
+        assert_eq!(cache.dirs(Path::new("/pool/a"), old + SETTLE), None);
+        assert_eq!(cache.dirs(Path::new("/pool/b"), SystemTime::now()), None);
+
+        // other configs have their own records
+        let configs = vec![Config::new("/pool", Kind::Folder, vec!["dist"], None)];
+        let cache = Cache::load(dir.path(), &configs);
+        assert_eq!(cache.dirs(Path::new("/pool/a"), old), None);
+
+        assert_eq!(clear(dir.path()).unwrap(), 1);
+    }
+
+    #[test]
+    fn disabled() {
+        let cache = Cache::default();
+        let old = SystemTime::UNIX_EPOCH;
+        cache.insert_dirs(PathBuf::from("/pool/a"), old, vec![]);
+        cache.save().unwrap();
+        assert_eq!(cache.dirs(Path::new("/pool/a"), old), None);
+    }
+}
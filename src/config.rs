@@ -1,3 +1,4 @@
+use crate::matcher::{self, Matcher, Rule};
 use clap::ValueEnum;
 use serde::Deserialize;
 use std::path::PathBuf;
@@ -22,6 +23,18 @@ pub struct Config {
     pub kind: Kind,
     pub patterns: Vec<String>,
     pub exclude: Option<Vec<String>>,
+    /// don't cross mount points while traversing `destination`.
+    #[serde(default)]
+    pub one_file_system: bool,
+    /// don't match items above this depth, children of `destination` have depth 1.
+    #[serde(default)]
+    pub min_depth: Option<usize>,
+    /// don't search below this depth.
+    #[serde(default)]
+    pub max_depth: Option<usize>,
+    /// rules matching items besides `patterns`, from library users.
+    #[serde(skip)]
+    pub rules: Vec<Rule>,
 }
 
 impl Config {
@@ -36,8 +49,34 @@ impl Config {
             kind,
             patterns: patterns.into_iter().map(Into::into).collect(),
             exclude: exclude.map(|e| e.into_iter().map(Into::into).collect()),
+            one_file_system: false,
+            min_depth: None,
+            max_depth: None,
+            rules: vec![],
         }
     }
+
+    /// Also match items matched by `matcher`.
+    pub fn rule<M: Matcher + 'static>(mut self, matcher: M) -> Config {
+        self.rules.push(Rule::new(matcher));
+        self
+    }
+
+    /// Matcher of `patterns` and `rules`.
+    pub(crate) fn matcher(&self) -> Box<dyn Matcher> {
+        let patterns = matcher::patterns(&self.kind, &self.patterns);
+        if self.rules.is_empty() {
+            return patterns;
+        }
+
+        let rules = self
+            .rules
+            .iter()
+            .map(|rule| Box::new(rule.clone()) as Box<dyn Matcher>);
+        Box::new(matcher::any(
+            std::iter::once(patterns).chain(rules).collect(),
+        ))
+    }
 }
 
 impl AsRef<Config> for Config {
@@ -75,6 +114,10 @@ mod tests {
                     String::from("release"),
                 ],
                 exclude: None,
+                one_file_system: false,
+                min_depth: None,
+                max_depth: None,
+                rules: vec![],
             }
         );
     }
@@ -113,6 +156,10 @@ mod tests {
                 kind: Kind::Folder,
                 patterns: vec![String::from("dist"), String::from("node_modules")],
                 exclude: None,
+                one_file_system: false,
+                min_depth: None,
+                max_depth: None,
+                rules: vec![],
             }
         );
 
@@ -127,8 +174,41 @@ mod tests {
                     kind: Kind::Folder,
                     patterns: vec![String::from("dist"), String::from("node_modules")],
                     exclude: None,
+                    one_file_system: false,
+                    min_depth: None,
+                    max_depth: None,
+                    rules: vec![],
                 }
             );
         }
     }
+
+    #[test]
+    fn parse_one_file_system() {
+        let configs: Vec<Config> = serde_json::from_str(
+            r#"[
+                {"destination": "/pool/node", "kind": "folder", "patterns": ["dist"]},
+                {"destination": "/pool/rust", "kind": "folder", "patterns": ["target"], "one_file_system": true}
+            ]"#,
+        )
+        .unwrap();
+        assert!(!configs[0].one_file_system);
+        assert!(configs[1].one_file_system);
+    }
+
+    #[test]
+    fn parse_depth() {
+        let configs: Vec<Config> = serde_json::from_str(
+            r#"[
+                {"destination": "/pool/node", "kind": "folder", "patterns": ["dist"]},
+                {"destination": "/pool/rust", "kind": "folder", "patterns": ["target"], "min_depth": 2, "max_depth": 4}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!((configs[0].min_depth, configs[0].max_depth), (None, None));
+        assert_eq!(
+            (configs[1].min_depth, configs[1].max_depth),
+            (Some(2), Some(4))
+        );
+    }
 }
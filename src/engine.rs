@@ -1,33 +1,170 @@
-use super::Kind;
-use clap::{ArgAction, Parser};
+use super::{Color, Format, Kind, Rate, Sort};
+use clap::{ArgAction, Parser, Subcommand};
+use serde::Deserialize;
 use std::path::PathBuf;
 
 #[derive(Default, Debug, Parser)]
 #[command(version, about, long_about = None)]
 pub struct Engine {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// config file (JSON format) with path(absolute or relative path).
-    #[arg(long, short)]
+    #[arg(long, short, global = true)]
     pub config: Option<PathBuf>,
 
     /// destination directory path(absolute or relative path).
-    #[arg(long, short)]
+    #[arg(long, short, global = true)]
     pub destination: Option<PathBuf>,
 
     /// what kind of item wants to remove.
-    #[arg(long, short, value_enum)]
+    #[arg(long, short, value_enum, global = true)]
     pub kind: Option<Kind>,
 
     /// List of patterns to remove(comma separated value).
-    #[arg(long, short, action = ArgAction::Append, value_delimiter = ',')]
+    #[arg(long, short, action = ArgAction::Append, value_delimiter = ',', global = true)]
     pub patterns: Option<Vec<String>>,
 
     /// List of items to be excluded from remove(comma separated value).
-    #[arg(long, short, action = ArgAction::Append, value_delimiter = ',')]
+    #[arg(long, short, action = ArgAction::Append, value_delimiter = ',', global = true)]
     pub exclude: Option<Vec<String>>,
 
     /// dry-run mode to check list of item to be removed.
-    #[arg(long)]
+    #[arg(long, global = true)]
     pub dryrun: bool,
+
+    /// remove matched items even if they contain tracked or uncommitted git content.
+    #[arg(long, global = true)]
+    pub force: bool,
+
+    /// don't cross mount points while traversing destination.
+    #[arg(long, global = true)]
+    pub one_file_system: bool,
+
+    /// don't match items above this depth, children of destination have depth 1.
+    #[arg(long, global = true)]
+    pub min_depth: Option<usize>,
+
+    /// don't search below this depth.
+    #[arg(long, global = true)]
+    pub max_depth: Option<usize>,
+
+    /// number of threads to scan and remove with, one per CPU by default.
+    #[arg(long, short, default_value_t = 0, global = true)]
+    pub jobs: usize,
+
+    /// lower CPU and I/O priority, to keep the system responsive.
+    #[arg(long, global = true)]
+    pub nice: bool,
+
+    /// limit removals to a number of entries per second (e.g. `500`), or of
+    /// bytes per second with a unit (e.g. `20MiB`).
+    #[arg(long, global = true)]
+    pub rate_limit: Option<Rate>,
+
+    /// rename matched folders aside and remove them in a background process,
+    /// so the command returns right away.
+    #[arg(long, global = true)]
+    pub background: bool,
+
+    /// don't reuse results of previous scans, nor save them.
+    #[arg(long, global = true)]
+    pub no_cache: bool,
+
+    /// wait for another run on the same destination to finish, instead of failing.
+    #[arg(long, conflicts_with = "no_lock", global = true)]
+    pub wait: bool,
+
+    /// don't lock destination against concurrent runs.
+    #[arg(long, global = true)]
+    pub no_lock: bool,
+
+    /// save plan (JSON format) of items to be removed, to apply it later.
+    #[arg(long, global = true)]
+    pub save_plan: Option<PathBuf>,
+
+    /// save a standalone report of the run, HTML (`.html`) or Markdown (`.md`).
+    #[arg(long, global = true)]
+    pub report: Option<PathBuf>,
+
+    /// output format.
+    #[arg(long, value_enum, default_value_t, global = true)]
+    pub format: Format,
+
+    /// when to use colors, `NO_COLOR` environment variable is respected.
+    #[arg(long, value_enum, default_value_t, global = true)]
+    pub color: Color,
+
+    /// print only the summary and errors.
+    #[arg(long, short, conflicts_with = "verbose", global = true)]
+    pub quiet: bool,
+
+    /// print excluded and skipped items too, `-vv` for traversal decisions.
+    #[arg(long, short, action = ArgAction::Count, global = true)]
+    pub verbose: u8,
+}
+
+#[derive(Deserialize, Debug, PartialEq, Subcommand)]
+pub enum Command {
+    /// apply a plan saved with `--save-plan`, exactly as it was reviewed.
+    Apply {
+        /// plan file (JSON format) with path(absolute or relative path).
+        plan: PathBuf,
+    },
+    /// report the biggest matched items, grouped by pattern and by project,
+    /// without removing anything.
+    Scan {
+        /// order of matched items.
+        #[arg(long, value_enum, default_value_t)]
+        sort: Sort,
+        /// number of items and projects to list.
+        #[arg(long, default_value_t = 20)]
+        top: usize,
+    },
+    /// tell whether a path would be removed, and which entry, pattern or exclude
+    /// decided it.
+    Explain {
+        /// path to explain (absolute or relative path).
+        path: PathBuf,
+    },
+    /// continue the last interrupted run of the configured destinations, with
+    /// the items it left.
+    Resume,
+    /// list past runs recorded in the audit log.
+    History {
+        #[command(subcommand)]
+        action: Option<HistoryCommand>,
+    },
+    /// manage results of previous scans, reused to skip unchanged folders.
+    Cache {
+        #[command(subcommand)]
+        action: CacheCommand,
+    },
+    /// remove folders detached by `--background`, read from stdin, run by
+    /// neaten itself.
+    #[command(hide = true)]
+    Purge {
+        /// destination the folders live under.
+        root: PathBuf,
+        /// device of destination, to stay on the same file system.
+        #[arg(long)]
+        device: Option<u64>,
+    },
+}
+
+#[derive(Deserialize, Debug, PartialEq, Subcommand)]
+pub enum HistoryCommand {
+    /// show removed items and errors of a past run.
+    Show {
+        /// id of the run, as listed by `neaten history`.
+        id: u64,
+    },
+}
+
+#[derive(Deserialize, Debug, PartialEq, Subcommand)]
+pub enum CacheCommand {
+    /// remove cached results of every config.
+    Clear,
 }
 
 #[cfg(test)]
@@ -189,4 +326,306 @@ mod tests {
         );
         assert!(engine.dryrun);
     }
+
+    #[test]
+    fn force() {
+        let args = vec![
+            "neaten",
+            "--config",
+            "/Users/abhinath/productive/pool/Project/neaten/sample/config.json",
+        ];
+        let engine = Engine::try_parse_from(args).unwrap();
+        assert!(!engine.force);
+
+        let args = vec![
+            "neaten",
+            "--config",
+            "/Users/abhinath/productive/pool/Project/neaten/sample/config.json",
+            "--force",
+        ];
+        let engine = Engine::try_parse_from(args).unwrap();
+        assert!(engine.force);
+    }
+
+    #[test]
+    fn save_plan_and_apply() {
+        let args = vec![
+            "neaten",
+            "--config",
+            "/Users/abhinath/productive/pool/Project/neaten/sample/config.json",
+            "--dryrun",
+            "--save-plan",
+            "plan.json",
+        ];
+        let engine = Engine::try_parse_from(args).unwrap();
+        assert_eq!(engine.save_plan.unwrap(), PathBuf::from("plan.json"));
+        assert!(engine.command.is_none());
+
+        let args = vec!["neaten", "apply", "plan.json"];
+        let engine = Engine::try_parse_from(args).unwrap();
+        assert_eq!(
+            engine.command.unwrap(),
+            Command::Apply {
+                plan: PathBuf::from("plan.json")
+            }
+        );
+    }
+
+    #[test]
+    fn report() {
+        let args = vec!["neaten", "-c", "config.json", "--report", "report.html"];
+        let engine = Engine::try_parse_from(args).unwrap();
+        assert_eq!(engine.report.unwrap(), PathBuf::from("report.html"));
+    }
+
+    #[test]
+    fn scan() {
+        let args = vec!["neaten", "scan", "-c", "config.json", "--sort", "age"];
+        let engine = Engine::try_parse_from(args).unwrap();
+        assert_eq!(engine.config.unwrap(), PathBuf::from("config.json"));
+        assert_eq!(
+            engine.command.unwrap(),
+            Command::Scan {
+                sort: Sort::Age,
+                top: 20
+            }
+        );
+    }
+
+    #[test]
+    fn explain() {
+        let args = vec!["neaten", "explain", "proj/target", "-c", "config.json"];
+        let engine = Engine::try_parse_from(args).unwrap();
+        assert_eq!(engine.config.unwrap(), PathBuf::from("config.json"));
+        assert_eq!(
+            engine.command.unwrap(),
+            Command::Explain {
+                path: PathBuf::from("proj/target")
+            }
+        );
+    }
+
+    #[test]
+    fn jobs() {
+        let args = vec!["neaten", "-c", "config.json", "--jobs", "4"];
+        let engine = Engine::try_parse_from(args).unwrap();
+        assert_eq!(engine.jobs, 4);
+
+        let args = vec!["neaten", "-c", "config.json"];
+        let engine = Engine::try_parse_from(args).unwrap();
+        assert_eq!(engine.jobs, 0);
+    }
+
+    #[test]
+    fn depth() {
+        let args = vec!["neaten", "-c", "config.json", "--min-depth", "2"];
+        let engine = Engine::try_parse_from(args).unwrap();
+        assert_eq!(engine.min_depth, Some(2));
+        assert_eq!(engine.max_depth, None);
+
+        let args = vec!["neaten", "scan", "-c", "config.json", "--max-depth", "3"];
+        let engine = Engine::try_parse_from(args).unwrap();
+        assert_eq!(engine.max_depth, Some(3));
+    }
+
+    #[test]
+    fn background() {
+        let args = vec!["neaten", "-c", "config.json", "--background"];
+        let engine = Engine::try_parse_from(args).unwrap();
+        assert!(engine.background);
+
+        let args = vec!["neaten", "purge", "/pool", "--device", "7"];
+        let engine = Engine::try_parse_from(args).unwrap();
+        assert_eq!(
+            engine.command.unwrap(),
+            Command::Purge {
+                root: PathBuf::from("/pool"),
+                device: Some(7),
+            }
+        );
+    }
+
+    #[test]
+    fn resume() {
+        let args = vec!["neaten", "resume"];
+        let engine = Engine::try_parse_from(args).unwrap();
+        assert_eq!(engine.command.unwrap(), Command::Resume);
+    }
+
+    #[test]
+    fn cache() {
+        let args = vec!["neaten", "-c", "config.json", "--no-cache"];
+        let engine = Engine::try_parse_from(args).unwrap();
+        assert!(engine.no_cache);
+
+        let args = vec!["neaten", "cache", "clear"];
+        let engine = Engine::try_parse_from(args).unwrap();
+        assert_eq!(
+            engine.command.unwrap(),
+            Command::Cache {
+                action: CacheCommand::Clear
+            }
+        );
+    }
+
+    #[test]
+    fn throttle() {
+        let args = vec![
+            "neaten",
+            "-c",
+            "config.json",
+            "--nice",
+            "--rate-limit",
+            "20MiB",
+        ];
+        let engine = Engine::try_parse_from(args).unwrap();
+        assert!(engine.nice);
+        assert_eq!(engine.rate_limit, Some(Rate::Bytes(20 * 1024 * 1024)));
+
+        let args = vec!["neaten", "-c", "config.json", "--rate-limit", "fast"];
+        assert!(Engine::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn history() {
+        let args = vec!["neaten", "history"];
+        let engine = Engine::try_parse_from(args).unwrap();
+        assert_eq!(engine.command.unwrap(), Command::History { action: None });
+
+        let args = vec!["neaten", "history", "show", "3"];
+        let engine = Engine::try_parse_from(args).unwrap();
+        assert_eq!(
+            engine.command.unwrap(),
+            Command::History {
+                action: Some(HistoryCommand::Show { id: 3 })
+            }
+        );
+    }
+
+    #[test]
+    fn format() {
+        let args = vec![
+            "neaten",
+            "--config",
+            "/Users/abhinath/productive/pool/Project/neaten/sample/config.json",
+        ];
+        let engine = Engine::try_parse_from(args).unwrap();
+        assert_eq!(engine.format, Format::Human);
+
+        let args = vec![
+            "neaten",
+            "--config",
+            "/Users/abhinath/productive/pool/Project/neaten/sample/config.json",
+            "--format",
+            "json",
+        ];
+        let engine = Engine::try_parse_from(args).unwrap();
+        assert_eq!(engine.format, Format::Json);
+    }
+
+    #[test]
+    fn color() {
+        let args = vec!["neaten", "apply", "plan.json"];
+        let engine = Engine::try_parse_from(args).unwrap();
+        assert_eq!(engine.color, Color::Auto);
+
+        let args = vec!["neaten", "--color", "never", "apply", "plan.json"];
+        let engine = Engine::try_parse_from(args).unwrap();
+        assert_eq!(engine.color, Color::Never);
+
+        let args = vec!["neaten", "--color", "sometimes", "apply", "plan.json"];
+        assert!(Engine::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn verbosity() {
+        let args = vec!["neaten", "-vv", "apply", "plan.json"];
+        let engine = Engine::try_parse_from(args).unwrap();
+        assert_eq!(engine.verbose, 2);
+        assert!(!engine.quiet);
+
+        let args = vec!["neaten", "--quiet", "apply", "plan.json"];
+        let engine = Engine::try_parse_from(args).unwrap();
+        assert!(engine.quiet);
+
+        let args = vec!["neaten", "-q", "-v", "apply", "plan.json"];
+        assert!(Engine::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn wait_conflicts_with_no_lock() {
+        let args = vec![
+            "neaten",
+            "--config",
+            "/Users/abhinath/productive/pool/Project/neaten/sample/config.json",
+            "--wait",
+            "--no-lock",
+        ];
+        assert!(Engine::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn force_after_command() {
+        let args = vec!["neaten", "scan", "-c", "config.json", "--force"];
+        let engine = Engine::try_parse_from(args).unwrap();
+        assert!(engine.force);
+    }
+
+    #[test]
+    fn lock_options_after_command() {
+        let args = vec!["neaten", "apply", "plan.json", "--wait"];
+        let engine = Engine::try_parse_from(args).unwrap();
+        assert!(engine.wait);
+
+        let args = vec!["neaten", "resume", "--no-lock"];
+        let engine = Engine::try_parse_from(args).unwrap();
+        assert!(engine.no_lock);
+
+        // conflicts still hold, wherever they are given
+        let args = vec!["neaten", "apply", "plan.json", "--wait", "--no-lock"];
+        assert!(Engine::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn plan_options_after_command() {
+        let args = vec!["neaten", "apply", "plan.json", "--dryrun"];
+        let engine = Engine::try_parse_from(args).unwrap();
+        assert!(engine.dryrun);
+
+        let args = vec!["neaten", "resume", "--save-plan", "plan.json"];
+        let engine = Engine::try_parse_from(args).unwrap();
+        assert_eq!(engine.save_plan.unwrap(), PathBuf::from("plan.json"));
+    }
+
+    #[test]
+    fn color_after_command() {
+        let args = vec!["neaten", "history", "--color", "never"];
+        let engine = Engine::try_parse_from(args).unwrap();
+        assert_eq!(engine.color, Color::Never);
+    }
+
+    #[test]
+    fn verbosity_after_command() {
+        let args = vec!["neaten", "apply", "plan.json", "-vv"];
+        let engine = Engine::try_parse_from(args).unwrap();
+        assert_eq!(engine.verbose, 2);
+
+        let args = vec!["neaten", "resume", "-q"];
+        let engine = Engine::try_parse_from(args).unwrap();
+        assert!(engine.quiet);
+    }
+
+    #[test]
+    fn report_after_command() {
+        let args = vec!["neaten", "apply", "plan.json", "--report", "report.md"];
+        let engine = Engine::try_parse_from(args).unwrap();
+        assert_eq!(engine.report.unwrap(), PathBuf::from("report.md"));
+    }
+
+    #[test]
+    fn background_after_command() {
+        let args = vec!["neaten", "apply", "plan.json", "--background"];
+        let engine = Engine::try_parse_from(args).unwrap();
+        assert!(engine.background);
+    }
 }
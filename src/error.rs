@@ -1,7 +1,9 @@
 // https://learning-rust.github.io/docs/custom-error-types/
 // https://www.youtube.com/watch?v=KrZ0nmpNVOw&t=1401s
 
+use crate::theme::{self, Style};
 use clap::Error as ClapError;
+use rayon::ThreadPoolBuildError;
 use serde_json::Error as SerdeJsonError;
 use std::{
     backtrace::Backtrace,
@@ -31,6 +33,8 @@ pub enum AppErrorKind {
     Usage,
     Functionality,
     Internal,
+    /// run stopped by Ctrl-C or `SIGTERM`.
+    Interrupted,
 }
 
 impl AppError {
@@ -42,10 +46,23 @@ impl AppError {
         }
     }
 
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Exit status of the process, 130 for an interruption as shells do for
+    /// SIGINT, 1 otherwise.
+    pub fn code(&self) -> i32 {
+        match self.kind {
+            AppErrorKind::Interrupted => 130,
+            _ => 1,
+        }
+    }
+
     pub fn exit(&self) -> ! {
         // TODO: replace eprintln!() with user defined/passing Formatter.
         eprintln!("{:?}", self);
-        std::process::exit(0);
+        std::process::exit(self.code());
     }
 
     // fn message(&self) -> String {
@@ -63,56 +80,79 @@ impl AppError {
     fn formatted(&self) -> String {
         match self.kind {
             AppErrorKind::Usage => self.formatted_usage(),
-            AppErrorKind::Functionality => self.formatted_functional(),
+            AppErrorKind::Functionality | AppErrorKind::Interrupted => self.formatted_functional(),
             AppErrorKind::Internal => self.formatted_internal(),
         }
     }
 
     fn formatted_usage(&self) -> String {
-        let mut msg = format!("\u{1b}[1m\u{1b}[31merror:\u{1b}[0m {}!\n\n", &self.message);
+        let mut msg = format!(
+            "{} {}!\n\n",
+            theme::paint(Style::Error, "error:"),
+            &self.message
+        );
         msg = format!(
-            "{}\u{1b}[1m\u{1b}[4mUsage:\u{1b}[0m \u{1b}[1m{}\u{1b}[0m [OPTIONS]\n\n",
+            "{}{} {} [OPTIONS]\n\n",
             msg,
-            env!("CARGO_PKG_NAME")
+            theme::paint(Style::Heading, "Usage:"),
+            theme::paint(Style::Bold, env!("CARGO_PKG_NAME"))
         );
         msg = format!(
-            "{}For more information, try '\u{1b}[1m--help\u{1b}[0m'.\n",
-            msg
+            "{}For more information, try '{}'.\n",
+            msg,
+            theme::paint(Style::Bold, "--help")
         );
         msg
     }
 
     fn formatted_functional(&self) -> String {
-        format!("\n\u{1b}[1m\u{1b}[31merror:\u{1b}[0m {}!\n", &self.message)
+        format!(
+            "\n{} {}!\n",
+            theme::paint(Style::Error, "error:"),
+            &self.message
+        )
     }
 
     fn formatted_internal(&self) -> String {
-        let mut msg = format!("\u{1b}[1m\u{1b}[31merror:\u{1b}[0m {}!\n\n", &self.message);
+        let mut msg = format!(
+            "{} {}!\n\n",
+            theme::paint(Style::Error, "error:"),
+            &self.message
+        );
         msg = format!(
-            "{}\u{1b}[1m{}: {}\u{1b}[0m\n\n",
+            "{}{}\n\n",
             msg,
-            env!("CARGO_PKG_NAME"),
-            env!("CARGO_PKG_VERSION")
+            theme::paint(
+                Style::Bold,
+                format!("{}: {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+            )
         );
         if is_backtrace_enabled() {
             msg = format!("{}Callstack:\n{}\n\n", msg, self.backtrace);
             msg = format!(
-                "{}please open an issue including this log on \u{1b}[4m{}/issues/new\n",
+                "{}please open an issue including this log on {}\n",
                 msg,
-                env!("CARGO_PKG_REPOSITORY")
+                theme::paint(
+                    Style::Underline,
+                    format!("{}/issues/new", env!("CARGO_PKG_REPOSITORY"))
+                )
             );
         } else {
             msg = format!("{}This is an internal error.\n", msg);
             msg = format!(
-                "{}You can capture callstack to send us by setting environment variable \u{1b}[4mRUST_LIB_BACKTRACE\u{1b}[0m before re-run {}.\n",
+                "{}You can capture callstack to send us by setting environment variable {} before re-run {}.\n",
                 msg,
+                theme::paint(Style::Underline, "RUST_LIB_BACKTRACE"),
                 env!("CARGO_PKG_NAME")
             );
             msg = format!("{}Or\n", msg);
             msg = format!(
-                "{}Open an issue including the error on \u{1b}[4m{}/issues/new\n",
+                "{}Open an issue including the error on {}\n",
                 msg,
-                env!("CARGO_PKG_REPOSITORY")
+                theme::paint(
+                    Style::Underline,
+                    format!("{}/issues/new", env!("CARGO_PKG_REPOSITORY"))
+                )
             );
         }
 
@@ -157,6 +197,12 @@ impl From<IoError> for AppError {
     }
 }
 
+impl From<ThreadPoolBuildError> for AppError {
+    fn from(e: ThreadPoolBuildError) -> Self {
+        Self::new(AppErrorKind::Functionality, e.to_string())
+    }
+}
+
 impl From<SerdeJsonError> for AppError {
     fn from(e: SerdeJsonError) -> Self {
         Self::new(AppErrorKind::Functionality, e.to_string())
@@ -169,16 +215,25 @@ impl AppErrorKind {
             Self::Usage => "Usage",
             Self::Functionality => "Functionality",
             Self::Internal => "Internal",
+            Self::Interrupted => "Interrupted",
         }
     }
 }
 
-// #[cfg(test)]
-// mod tests {
-//     use super::*;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-//     #[test]
-//     fn new() {
-//         assert!(true);
-//     }
-// }
+    #[test]
+    fn code() {
+        assert_eq!(AppError::new(AppErrorKind::Usage, "usage").code(), 1);
+        assert_eq!(
+            AppError::new(AppErrorKind::Functionality, "locked").code(),
+            1
+        );
+        assert_eq!(
+            AppError::new(AppErrorKind::Interrupted, "interrupted").code(),
+            130
+        );
+    }
+}
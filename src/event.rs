@@ -0,0 +1,141 @@
+//! Streaming events of a run, one JSON object per line (NDJSON).
+//!
+//! Every line carries the `version` of the schema and the `event` name, other
+//! fields depend on the event. New fields or events may be added within a
+//! version, existing ones are only changed along with a new version.
+
+use crate::size::Size;
+use serde::Serialize;
+use std::{
+    io::{self, Write},
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Version of the event schema.
+pub const VERSION: u32 = 1;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    /// started to scan destination of a config entry.
+    ScanStarted {
+        destination: &'a Path,
+    },
+    /// path ignored because of `exclude`.
+    Excluded {
+        path: &'a Path,
+    },
+    /// path matched with patterns and planned to be removed.
+    Matched {
+        path: &'a Path,
+    },
+    /// matched path which is kept.
+    Skipped {
+        path: &'a Path,
+        reason: &'a str,
+    },
+    /// matched path which would be removed (dry-run).
+    WouldRemove {
+        path: &'a Path,
+        size: Size,
+    },
+    Removed {
+        path: &'a Path,
+        size: Size,
+    },
+    /// folder renamed aside, removed by a background process after the run.
+    Detached {
+        path: &'a Path,
+    },
+    Error {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        path: Option<&'a Path>,
+        message: &'a str,
+    },
+    EntryFinished {
+        destination: &'a Path,
+        removed: usize,
+        size: Size,
+        /// folders still in use, being freed in background.
+        detached: usize,
+        errors: usize,
+        duration_ms: u64,
+    },
+    RunFinished {
+        dryrun: bool,
+        removed: usize,
+        size: Size,
+        /// folders still in use, being freed in background.
+        detached: usize,
+        errors: usize,
+        duration_ms: u64,
+    },
+}
+
+#[derive(Serialize)]
+struct Line<'a> {
+    version: u32,
+    #[serde(flatten)]
+    event: &'a Event<'a>,
+}
+
+/// Stream events to stdout.
+pub fn enable(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn emit(event: Event) {
+    if ENABLED.load(Ordering::Relaxed) {
+        let mut stdout = io::stdout().lock();
+        // a closed stdout must not stop the run
+        let _ = writeln!(stdout, "{}", self::line(&event));
+        let _ = stdout.flush();
+    }
+}
+
+fn line(event: &Event) -> String {
+    serde_json::to_string(&Line {
+        version: VERSION,
+        event,
+    })
+    .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schema() {
+        assert_eq!(
+            line(&Event::Matched {
+                path: Path::new("/pool/rust/target")
+            }),
+            r#"{"version":1,"event":"matched","path":"/pool/rust/target"}"#
+        );
+        assert_eq!(
+            line(&Event::Error {
+                path: None,
+                message: "permission denied"
+            }),
+            r#"{"version":1,"event":"error","message":"permission denied"}"#
+        );
+        assert_eq!(
+            line(&Event::RunFinished {
+                dryrun: true,
+                removed: 2,
+                size: Size {
+                    apparent: 10,
+                    disk: 4096
+                },
+                detached: 0,
+                errors: 0,
+                duration_ms: 5,
+            }),
+            r#"{"version":1,"event":"run_finished","dryrun":true,"removed":2,"size":{"apparent":10,"disk":4096},"detached":0,"errors":0,"duration_ms":5}"#
+        );
+    }
+}
@@ -0,0 +1,155 @@
+use crate::{
+    lock::{self, Lock},
+    manager::helper,
+    plan::{Entry, Plan},
+    report::{EntryReport, Report},
+};
+use std::time::Instant;
+
+/// Removes items of a [`Plan`].
+///
+/// Items replaced or modified since they were planned are skipped, folders
+/// of git repositories were already left out by the
+/// [`Scanner`](crate::Scanner). Nothing is printed, the outcome of each item
+/// is in the [`Report`].
+///
+/// ```no_run
+/// use neaten::{Config, Executor, Kind, Scanner};
+///
+/// let configs = vec![Config::new("/usr/sample/node", Kind::Folder, vec!["node_modules"], None)];
+/// let (mut plan, _) = Scanner::new(&configs).scan();
+/// // keep what is still in use
+/// plan.retain(|item| !item.path.starts_with("/usr/sample/node/app"));
+/// let report = Executor::new().execute(&plan)?;
+/// println!("{} items removed", report.removed());
+/// # Ok::<(), neaten::AppError>(())
+/// ```
+#[derive(Debug, Default)]
+pub struct Executor {
+    dryrun: bool,
+    background: bool,
+    lock: bool,
+    wait: bool,
+}
+
+impl Executor {
+    pub fn new() -> Executor {
+        Executor {
+            lock: true,
+            ..Default::default()
+        }
+    }
+
+    /// Report what would be removed, without removing anything.
+    pub fn dryrun(mut self, dryrun: bool) -> Executor {
+        self.dryrun = dryrun;
+        self
+    }
+
+    /// Lock destinations, as `neaten` runs do, so that runs don't apply plans
+    /// on the same destination concurrently. Enabled by default.
+    pub fn lock(mut self, lock: bool) -> Executor {
+        self.lock = lock;
+        self
+    }
+
+    /// Wait for a locked destination, instead of failing.
+    pub fn wait(mut self, wait: bool) -> Executor {
+        self.wait = wait;
+        self
+    }
+
+    /// Detach folders and remove them from a `neaten` process started once
+    /// they are all detached, only for the `neaten` binary itself.
+    pub(crate) fn background(mut self, background: bool) -> Executor {
+        self.background = background;
+        self
+    }
+
+    /// Remove items of `plan`, return one report entry per plan entry.
+    pub fn execute(&self, plan: &Plan) -> crate::Result<Report> {
+        let start = Instant::now();
+        let _locks: Vec<Lock> = match self.lock && !self.dryrun {
+            true => lock::acquire_all(
+                plan.entries.iter().map(|entry| &entry.destination),
+                self.wait,
+            )?,
+            false => vec![],
+        };
+
+        let mut report = Report::new(self.dryrun);
+        report.entries = plan
+            .entries
+            .iter()
+            .map(|entry| EntryReport::new(&entry.destination))
+            .collect();
+        self.apply(plan, &mut report);
+        report.finish(start.elapsed());
+        Ok(report)
+    }
+
+    /// Remove items of `plan`, adding their outcome to `report` which has an
+    /// entry per plan entry. Return the items left by an interruption.
+    pub(crate) fn apply(&self, plan: &Plan, report: &mut Report) -> Plan {
+        let mut left = Plan::new();
+        for (entry, entry_report) in plan.entries.iter().zip(&mut report.entries) {
+            let start = Instant::now();
+            let items = helper::apply(entry, self.dryrun, self.background, entry_report);
+            if !items.is_empty() {
+                left.add(Entry {
+                    items,
+                    ..entry.clone()
+                });
+            }
+            entry_report.elapsed(start.elapsed());
+            entry_report.finish();
+        }
+        left
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Config, Kind, Scanner, report::Status};
+    use std::fs;
+
+    #[test]
+    fn execute_filtered_plan() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("old/target")).unwrap();
+        fs::create_dir_all(dir.path().join("new/target")).unwrap();
+
+        let configs = vec![Config::new(dir.path(), Kind::Folder, vec!["target"], None)];
+        let (mut plan, _) = Scanner::new(&configs).scan();
+        assert_eq!(plan.len(), 2);
+        plan.retain(|item| !item.path.starts_with(dir.path().join("new")));
+
+        let report = Executor::new().dryrun(true).execute(&plan).unwrap();
+        assert_eq!(report.entries[0].items[0].status, Status::WouldRemove);
+        assert!(dir.path().join("old/target").exists());
+
+        let report = Executor::new().execute(&plan).unwrap();
+        assert_eq!(report.removed(), 1);
+        assert!(!dir.path().join("old/target").exists());
+        assert!(dir.path().join("new/target").exists());
+    }
+
+    #[test]
+    fn execute_files_then_their_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("proj/target")).unwrap();
+        fs::write(dir.path().join("proj/target/build.log"), "log").unwrap();
+
+        let configs = vec![
+            Config::new(dir.path(), Kind::File, vec!["log"], None),
+            Config::new(dir.path(), Kind::Folder, vec!["target"], None),
+        ];
+        let (plan, _) = Scanner::new(&configs).scan();
+        let report = Executor::new().execute(&plan).unwrap();
+
+        assert_eq!(report.errors(), 0);
+        assert_eq!(report.entries[1].items[0].status, Status::Removed);
+        assert!(!dir.path().join("proj/target").exists());
+    }
+}
@@ -0,0 +1,160 @@
+//! Why a path would be removed or kept by a config entry.
+
+use serde::Serialize;
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    path::PathBuf,
+};
+
+/// What decided the fate of a path, for one config entry.
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(tag = "decision", rename_all = "snake_case")]
+pub enum Decision {
+    /// path doesn't exist.
+    Missing,
+    /// path isn't inside destination of the entry.
+    Outside,
+    /// destination itself is never removed.
+    Destination,
+    /// path, or one of its parents, is excluded.
+    Excluded { path: PathBuf, exclude: String },
+    /// path, or one of its parents, is on another file system.
+    OtherFileSystem { path: PathBuf },
+    /// path, or one of its parents, is a folder detached by a background
+    /// removal, it's reclaimed when the process removing it is gone.
+    Detached { path: PathBuf, reclaimed: bool },
+    /// one of the parents is a symbolic link, which is never followed.
+    Symlink { path: PathBuf },
+    /// one of the parents matches a pattern, so it's removed along with it.
+    Inside { path: PathBuf, pattern: String },
+    /// path, or one of its parents, matches a pattern but it's protected, e.g. by git.
+    Protected {
+        path: PathBuf,
+        pattern: String,
+        reason: String,
+    },
+    /// path, or one of its parents, is below max depth, which isn't searched.
+    TooDeep { max_depth: usize },
+    /// path matches a pattern but is above min depth.
+    TooShallow { min_depth: usize },
+    /// path matches a pattern.
+    Matched { pattern: String },
+    /// path doesn't match any pattern.
+    NoMatch,
+}
+
+impl Decision {
+    /// Whether a run would remove the path.
+    pub fn removed(&self) -> bool {
+        matches!(
+            self,
+            Decision::Inside { .. }
+                | Decision::Matched { .. }
+                | Decision::Detached {
+                    reclaimed: true,
+                    ..
+                }
+        )
+    }
+}
+
+impl Display for Decision {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Decision::Missing => write!(f, "kept, path doesn't exist"),
+            Decision::Outside => write!(f, "kept, path is outside destination"),
+            Decision::Destination => write!(f, "kept, destination itself is never removed"),
+            Decision::Excluded { path, exclude } => {
+                write!(f, "kept, {:?} is excluded by {:?}", path, exclude)
+            }
+            Decision::OtherFileSystem { path } => {
+                write!(f, "kept, {:?} is on another file system", path)
+            }
+            Decision::Detached {
+                path,
+                reclaimed: false,
+            } => write!(
+                f,
+                "kept, {:?} is being removed in background by another run",
+                path
+            ),
+            Decision::Detached {
+                path,
+                reclaimed: true,
+            } => write!(
+                f,
+                "removed, {:?} was left by a background removal which is gone",
+                path
+            ),
+            Decision::Symlink { path } => {
+                write!(
+                    f,
+                    "kept, {:?} is a symbolic link which is never followed",
+                    path
+                )
+            }
+            Decision::Inside { path, pattern } => {
+                write!(
+                    f,
+                    "removed, inside {:?} which matches pattern {:?}",
+                    path, pattern
+                )
+            }
+            Decision::Protected {
+                path,
+                pattern,
+                reason,
+            } => write!(
+                f,
+                "kept, {:?} matches pattern {:?} but {}, use --force to remove it",
+                path, pattern, reason
+            ),
+            Decision::TooDeep { max_depth } => {
+                write!(f, "kept, path is below max depth {}", max_depth)
+            }
+            Decision::TooShallow { min_depth } => {
+                write!(
+                    f,
+                    "kept, matches a pattern but is above min depth {}",
+                    min_depth
+                )
+            }
+            Decision::Matched { pattern } => write!(f, "removed, matches pattern {:?}", pattern),
+            Decision::NoMatch => write!(f, "kept, doesn't match any pattern"),
+        }
+    }
+}
+
+/// Decision of a config entry.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Explanation {
+    /// position of the entry in the config, starting at 1.
+    pub entry: usize,
+    pub destination: PathBuf,
+    pub removed: bool,
+    #[serde(flatten)]
+    pub decision: Decision,
+}
+
+impl Explanation {
+    pub fn new(entry: usize, destination: PathBuf, decision: Decision) -> Explanation {
+        Explanation {
+            entry,
+            destination,
+            removed: decision.removed(),
+            decision,
+        }
+    }
+}
+
+impl Display for Explanation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "Entry {} ({}): {}",
+            self.entry,
+            self.destination.display(),
+            self.decision
+        )
+    }
+}
@@ -0,0 +1,428 @@
+//! Standalone HTML or Markdown document of a run, to share it with people who
+//! don't use the terminal.
+
+use crate::{
+    error::{AppError, AppErrorKind},
+    history,
+    report::{ItemReport, Report, Status},
+    size::Bytes,
+};
+use std::{
+    fmt::{Result as FmtResult, Write},
+    fs,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Number of largest items listed in the document.
+const LARGEST: usize = 20;
+/// Number of skipped or excluded paths and of errors listed in the document,
+/// the JSON report (`--format json`) has all of them.
+const LISTED: usize = 100;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Document {
+    Html,
+    Markdown,
+}
+
+impl Document {
+    /// Document type from the extension of `path`.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> crate::Result<Document> {
+        let extn = path
+            .as_ref()
+            .extension()
+            .unwrap_or_default()
+            .to_str()
+            .unwrap_or_default()
+            .to_lowercase();
+        match extn.as_str() {
+            "html" | "htm" => Ok(Document::Html),
+            "md" | "markdown" => Ok(Document::Markdown),
+            _ => Err(AppError::new(
+                AppErrorKind::Usage,
+                "report file is not a HTML or Markdown file, please provide a .html or .md file",
+            )),
+        }
+    }
+}
+
+/// Write document of `report` to `path`, its type depends on the extension.
+pub fn save<P: AsRef<Path>>(report: &Report, path: P) -> crate::Result<()> {
+    let path = path.as_ref();
+    let document = Document::from_path(path)?;
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    fs::write(path, self::render(report, document, timestamp))?;
+    Ok(())
+}
+
+pub fn render(report: &Report, document: Document, timestamp: u64) -> String {
+    let sections = self::sections(report, timestamp);
+    let mut doc = String::new();
+    // writing to a string never fails
+    let _ = match document {
+        Document::Html => self::html(&sections, &mut doc),
+        Document::Markdown => self::markdown(&sections, &mut doc),
+    };
+    doc
+}
+
+struct Table {
+    heading: String,
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+    /// number of rows left out.
+    more: usize,
+}
+
+impl Table {
+    fn new(heading: &str, header: &[&str], rows: Vec<Vec<String>>) -> Table {
+        Table {
+            heading: heading.to_string(),
+            header: header.iter().map(|name| name.to_string()).collect(),
+            rows,
+            more: 0,
+        }
+    }
+
+    /// Table of the first `LISTED` of `rows`.
+    fn listed<I: Iterator<Item = Vec<String>>>(
+        heading: &str,
+        header: &[&str],
+        mut rows: I,
+    ) -> Table {
+        let listed = rows.by_ref().take(LISTED).collect();
+        let mut table = Table::new(heading, header, listed);
+        table.more = rows.count();
+        table
+    }
+}
+
+/// Content of the document, independent of its type.
+struct Sections {
+    title: String,
+    /// (name, value) pairs of the overview.
+    overview: Vec<(String, String)>,
+    /// empty tables are left out.
+    tables: Vec<Table>,
+}
+
+fn sections(report: &Report, timestamp: u64) -> Sections {
+    let size = report.size();
+    let mut overview = vec![
+        (String::from("Date"), history::utc(timestamp)),
+        (
+            String::from("Mode"),
+            String::from(if report.dryrun { "dry-run" } else { "run" }),
+        ),
+        (
+            String::from(if report.dryrun { "Would free" } else { "Freed" }),
+            format!("{} ({} apparent)", Bytes(size.disk), Bytes(size.apparent)),
+        ),
+        (String::from("Items"), report.removed().to_string()),
+        (String::from("Errors"), report.errors().to_string()),
+        (
+            String::from("Duration"),
+            format!("{} ms", report.duration_ms),
+        ),
+    ];
+    let detached = report.detached();
+    if detached > 0 {
+        overview.insert(
+            3,
+            (
+                String::from("Freed in background"),
+                format!("{} folders", detached),
+            ),
+        );
+    }
+
+    let destinations = report
+        .entries
+        .iter()
+        .map(|entry| {
+            vec![
+                entry.destination.display().to_string(),
+                entry.removed.to_string(),
+                Bytes(entry.size.apparent).to_string(),
+                Bytes(entry.size.disk).to_string(),
+                entry.errors.len().to_string(),
+                format!("{} ms", entry.duration_ms),
+            ]
+        })
+        .collect();
+
+    // biggest first, then by path so the document is stable
+    let mut largest: Vec<&ItemReport> = report
+        .entries
+        .iter()
+        .flat_map(|entry| &entry.items)
+        .filter(|item| {
+            matches!(
+                item.status,
+                Status::Removed | Status::Detached | Status::WouldRemove
+            )
+        })
+        .collect();
+    largest.sort_by(|a, b| {
+        let size = |item: &ItemReport| item.size.unwrap_or_default().disk;
+        size(b).cmp(&size(a)).then(a.path.cmp(&b.path))
+    });
+    let largest = largest
+        .into_iter()
+        .take(LARGEST)
+        .map(|item| {
+            let size = item.size.unwrap_or_default();
+            vec![
+                item.path.display().to_string(),
+                Bytes(size.apparent).to_string(),
+                Bytes(size.disk).to_string(),
+            ]
+        })
+        .collect();
+
+    let skipped = report
+        .entries
+        .iter()
+        .flat_map(|entry| &entry.items)
+        .filter(|item| item.status == Status::Skipped)
+        .map(|item| {
+            vec![
+                item.path.display().to_string(),
+                item.reason.clone().unwrap_or_default(),
+            ]
+        });
+
+    let excluded = report
+        .entries
+        .iter()
+        .flat_map(|entry| &entry.excluded)
+        .map(|path| vec![path.display().to_string()]);
+
+    let errors = report
+        .entries
+        .iter()
+        .flat_map(|entry| entry.errors.iter().map(|error| vec![error.clone()]));
+
+    Sections {
+        title: String::from("neaten report"),
+        overview,
+        tables: vec![
+            Table::new(
+                "Destinations",
+                &[
+                    "Destination",
+                    "Items",
+                    "Apparent",
+                    "On disk",
+                    "Errors",
+                    "Duration",
+                ],
+                destinations,
+            ),
+            Table::new(
+                if report.dryrun {
+                    "Largest items to remove"
+                } else {
+                    "Largest items removed"
+                },
+                &["Path", "Apparent", "On disk"],
+                largest,
+            ),
+            Table::listed("Skipped", &["Path", "Reason"], skipped),
+            Table::listed("Excluded", &["Path"], excluded),
+            Table::listed("Errors", &["Error"], errors),
+        ]
+        .into_iter()
+        .filter(|table| !table.rows.is_empty())
+        .collect(),
+    }
+}
+
+fn html(sections: &Sections, doc: &mut String) -> FmtResult {
+    doc.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n");
+    doc.push_str("<meta charset=\"utf-8\">\n");
+    writeln!(doc, "<title>{}</title>", self::escape(&sections.title))?;
+    doc.push_str(
+        "<style>\nbody { font-family: sans-serif; margin: 2em; }\n\
+         table { border-collapse: collapse; margin-bottom: 2em; }\n\
+         th, td { border: 1px solid #ccc; padding: 4px 8px; text-align: left; }\n\
+         th { background: #f4f4f4; }\n</style>\n",
+    );
+    writeln!(
+        doc,
+        "</head>\n<body>\n<h1>{}</h1>\n<table>",
+        self::escape(&sections.title)
+    )?;
+    for (name, value) in &sections.overview {
+        writeln!(
+            doc,
+            "<tr><th>{}</th><td>{}</td></tr>",
+            self::escape(name),
+            self::escape(value)
+        )?;
+    }
+    doc.push_str("</table>\n");
+
+    for table in &sections.tables {
+        write!(
+            doc,
+            "<h2>{}</h2>\n<table>\n<tr>",
+            self::escape(&table.heading)
+        )?;
+        for name in &table.header {
+            write!(doc, "<th>{}</th>", self::escape(name))?;
+        }
+        doc.push_str("</tr>\n");
+        for row in &table.rows {
+            doc.push_str("<tr>");
+            for cell in row {
+                write!(doc, "<td>{}</td>", self::escape(cell))?;
+            }
+            doc.push_str("</tr>\n");
+        }
+        doc.push_str("</table>\n");
+        if table.more > 0 {
+            writeln!(doc, "<p>{} more not listed.</p>", table.more)?;
+        }
+    }
+    doc.push_str("</body>\n</html>\n");
+    Ok(())
+}
+
+fn markdown(sections: &Sections, doc: &mut String) -> FmtResult {
+    writeln!(doc, "# {}\n", self::escape_md(&sections.title))?;
+    for (name, value) in &sections.overview {
+        writeln!(
+            doc,
+            "- **{}:** {}",
+            self::escape_md(name),
+            self::escape_md(value)
+        )?;
+    }
+
+    for table in &sections.tables {
+        writeln!(doc, "\n## {}\n", self::escape_md(&table.heading))?;
+        self::row(&table.header, doc)?;
+        writeln!(doc, "|{}", " --- |".repeat(table.header.len()))?;
+        for row in &table.rows {
+            self::row(row, doc)?;
+        }
+        if table.more > 0 {
+            writeln!(doc, "\n{} more not listed.", table.more)?;
+        }
+    }
+    Ok(())
+}
+
+fn row(cells: &[String], doc: &mut String) -> FmtResult {
+    doc.push('|');
+    for cell in cells {
+        write!(doc, " {} |", self::escape_md(cell))?;
+    }
+    doc.push('\n');
+    Ok(())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+fn escape_md(text: &str) -> String {
+    text.chars().fold(String::new(), |mut escaped, c| {
+        if "\\`*_[]<>|#".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+        escaped
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{report::EntryReport, size::Size};
+
+    fn report() -> Report {
+        let mut report = Report::new(false);
+        let mut entry = EntryReport::new("/pool/rust");
+        entry.exclude("/pool/rust/vendor");
+        entry.removed(
+            "/pool/rust/small/target",
+            Size {
+                apparent: 10,
+                disk: 4096,
+            },
+        );
+        entry.removed(
+            "/pool/rust/big|<b>/target",
+            Size {
+                apparent: 1024 * 1024,
+                disk: 1024 * 1024,
+            },
+        );
+        entry.error("permission denied");
+        report.entries.push(entry);
+        report
+    }
+
+    #[test]
+    fn document_from_path() {
+        assert_eq!(Document::from_path("report.html").unwrap(), Document::Html);
+        assert_eq!(
+            Document::from_path("report.MD").unwrap(),
+            Document::Markdown
+        );
+        assert!(Document::from_path("report.txt").is_err());
+    }
+
+    #[test]
+    fn markdown_report() {
+        let doc = render(&report(), Document::Markdown, 0);
+        assert!(doc.starts_with("# neaten report\n"));
+        assert!(doc.contains("- **Date:** 1970-01-01 00:00:00 UTC\n"));
+        assert!(doc.contains("| /pool/rust | 2 |"));
+
+        // biggest first, table cells escaped
+        let big = doc.find("big\\|\\<b\\>/target").unwrap();
+        let small = doc.find("small/target").unwrap();
+        assert!(big < small);
+
+        assert!(doc.contains("## Excluded\n\n| Path |\n| --- |\n| /pool/rust/vendor |\n"));
+        assert!(doc.contains("## Errors\n\n| Error |\n| --- |\n| permission denied |\n"));
+        assert!(!doc.contains("## Skipped"));
+    }
+
+    #[test]
+    fn html_report() {
+        let doc = render(&report(), Document::Html, 0);
+        assert!(doc.starts_with("<!DOCTYPE html>"));
+        assert!(doc.contains("<h2>Largest items removed</h2>"));
+        assert!(doc.contains("<td>/pool/rust/big|&lt;b&gt;/target</td>"));
+        assert!(!doc.contains("<b>"));
+        assert!(doc.ends_with("</html>\n"));
+    }
+
+    #[test]
+    fn listed_rows() {
+        let mut report = report();
+        for index in 0..LISTED + 5 {
+            report.entries[0].exclude(format!("/pool/rust/vendor{}", index));
+        }
+
+        // one more excluded path from `report`
+        let doc = render(&report, Document::Markdown, 0);
+        assert!(doc.contains("| /pool/rust/vendor98 |\n\n6 more not listed.\n"));
+        assert!(!doc.contains("vendor99"));
+        let doc = render(&report, Document::Html, 0);
+        assert!(doc.contains("</table>\n<p>6 more not listed.</p>\n"));
+        assert_eq!(doc.matches("<td>/pool/rust/vendor").count(), LISTED);
+    }
+}
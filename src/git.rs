@@ -0,0 +1,176 @@
+use crate::stats;
+use std::{
+    io::ErrorKind,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+/// Reason why a matched item inside a git working tree must not be removed.
+#[derive(Debug, PartialEq)]
+pub enum Protection {
+    /// item is (or contains) a file which is tracked by git.
+    Tracked,
+    /// item contains changes which are not committed yet.
+    Uncommitted,
+    /// git failed, e.g. on a repository of another user, so the item may
+    /// hold content managed by git.
+    Unknown(String),
+}
+
+impl Protection {
+    pub fn message(&self) -> &str {
+        match self {
+            Self::Tracked => "contains tracked files",
+            Self::Uncommitted => "contains uncommitted changes",
+            Self::Unknown(message) => message,
+        }
+    }
+}
+
+/// Check whether removing `path` would lose content managed by git.
+///
+/// It shells out to the local `git`, so paths outside of a working tree are
+/// reported as unprotected. Whenever git can't tell, e.g. it fails or it's
+/// missing while the path is in a working tree, the path is protected.
+pub fn protection<P: AsRef<Path>>(path: P) -> Option<Protection> {
+    let path = path.as_ref();
+    stats::stat();
+    let dir = if path.is_dir() { path } else { path.parent()? };
+
+    // tracked (or staged) files
+    let tracked = match self::run(dir, &["ls-files", "-z", "--"], path) {
+        Ok(tracked) => tracked?,
+        Err(protection) => return Some(protection),
+    };
+    if !tracked.is_empty() {
+        return Some(Protection::Tracked);
+    }
+
+    // modified or untracked (but not ignored) files
+    let status = match self::run(dir, &["status", "--porcelain", "-z", "--"], path) {
+        Ok(status) => status?,
+        Err(protection) => return Some(protection),
+    };
+    if !status.is_empty() {
+        return Some(Protection::Uncommitted);
+    }
+
+    None
+}
+
+/// Output of git, none outside of a working tree.
+fn run(dir: &Path, args: &[&str], path: &Path) -> Result<Option<Vec<u8>>, Protection> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .arg(path)
+        // messages to tell a failure from a path outside of a working tree
+        .env("LC_ALL", "C")
+        .stdin(Stdio::null())
+        .output();
+    let output = match output {
+        Ok(output) => output,
+        // without git, only paths outside of a working tree are safe
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            let repository = dir.ancestors().any(|dir| {
+                stats::stat();
+                dir.join(".git").exists()
+            });
+            return match repository {
+                true => Err(Protection::Unknown(String::from(
+                    "is in a git working tree but git isn't installed",
+                ))),
+                false => Ok(None),
+            };
+        }
+        Err(e) => return Err(Protection::Unknown(format!("can't run git: {}", e))),
+    };
+
+    if output.status.success() {
+        return Ok(Some(output.stdout));
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if stderr.contains("not a git repository") {
+        return Ok(None);
+    }
+    let message = stderr.lines().next().unwrap_or_default().trim();
+    Err(Protection::Unknown(format!("git failed: {}", message)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["-c", "user.name=neaten", "-c", "user.email=neaten@test"])
+            .args(args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    fn repo() -> TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        git(dir.path(), &["init"]);
+        fs::write(dir.path().join(".gitignore"), "target/\n").unwrap();
+        git(dir.path(), &["add", ".gitignore"]);
+        git(dir.path(), &["commit", "-m", "init"]);
+        dir
+    }
+
+    #[test]
+    fn outside_repository() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("build")).unwrap();
+        assert_eq!(protection(dir.path().join("build")), None);
+    }
+
+    #[test]
+    fn ignored_folder() {
+        let dir = repo();
+        let target = dir.path().join("target");
+        fs::create_dir(&target).unwrap();
+        fs::write(target.join("app"), "binary").unwrap();
+        assert_eq!(protection(&target), None);
+    }
+
+    #[test]
+    fn tracked_folder() {
+        let dir = repo();
+        let build = dir.path().join("build");
+        fs::create_dir(&build).unwrap();
+        fs::write(build.join("keep.txt"), "on purpose").unwrap();
+        git(dir.path(), &["add", "build"]);
+        git(dir.path(), &["commit", "-m", "build"]);
+        assert_eq!(protection(&build), Some(Protection::Tracked));
+    }
+
+    #[test]
+    fn uncommitted_folder() {
+        let dir = repo();
+        let build = dir.path().join("build");
+        fs::create_dir(&build).unwrap();
+        fs::write(build.join("notes.txt"), "work in progress").unwrap();
+        assert_eq!(protection(&build), Some(Protection::Uncommitted));
+    }
+
+    #[test]
+    fn failing_git() {
+        let dir = repo();
+        let build = dir.path().join("build");
+        fs::create_dir(&build).unwrap();
+        fs::write(dir.path().join(".git/config"), "[broken").unwrap();
+        assert!(matches!(
+            protection(&build),
+            Some(Protection::Unknown(message)) if message.starts_with("git failed: fatal:")
+        ));
+    }
+}
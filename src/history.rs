@@ -0,0 +1,315 @@
+//! Audit log of runs, one JSON record per line.
+//!
+//! Records are appended to `$XDG_STATE_HOME/neaten/history.jsonl` (or
+//! `~/.local/state/neaten/history.jsonl`, `%LOCALAPPDATA%\neaten\history.jsonl`
+//! on Windows) and never rewritten.
+
+use crate::{
+    error::{AppError, AppErrorKind},
+    report::{Report, Status},
+    size::{Bytes, Size},
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    env,
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Record of a run.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct Record {
+    pub id: u64,
+    /// seconds since UNIX epoch.
+    pub timestamp: u64,
+    pub user: String,
+    /// config (or plan) file of the run, if any.
+    pub config: Option<PathBuf>,
+    /// SHA-256 of the config (or plan) file.
+    pub config_hash: Option<String>,
+    pub dryrun: bool,
+    pub destinations: Vec<PathBuf>,
+    /// removed (or to be removed in dry-run) items.
+    pub removed: Vec<Removed>,
+    pub size: Size,
+    /// number of detached folders, freed in background after the run.
+    #[serde(default)]
+    pub detached: usize,
+    pub errors: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct Removed {
+    pub path: PathBuf,
+    pub size: Size,
+}
+
+impl Record {
+    pub fn new(report: &Report, config: Option<&Path>) -> Record {
+        Record {
+            id: 0,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default(),
+            user: env::var("USER")
+                .or(env::var("USERNAME"))
+                .unwrap_or(String::from("unknown")),
+            config: config.map(Path::to_path_buf),
+            config_hash: config.and_then(|config| self::hash(config).ok()),
+            dryrun: report.dryrun,
+            destinations: report
+                .entries
+                .iter()
+                .map(|entry| entry.destination.clone())
+                .collect(),
+            removed: report
+                .entries
+                .iter()
+                .flat_map(|entry| &entry.items)
+                .filter(|item| {
+                    matches!(
+                        item.status,
+                        Status::Removed | Status::Detached | Status::WouldRemove
+                    )
+                })
+                .map(|item| Removed {
+                    path: item.path.clone(),
+                    size: item.size.unwrap_or_default(),
+                })
+                .collect(),
+            size: report.size(),
+            detached: report.detached(),
+            errors: report
+                .entries
+                .iter()
+                .flat_map(|entry| entry.errors.clone())
+                .collect(),
+        }
+    }
+
+    /// One line per run, for `neaten history`.
+    pub fn line(&self) -> String {
+        format!(
+            "{:>5}  {}  {:<12}  {:<7}  {:>6}  {:>10}  {:>6}",
+            self.id,
+            self::utc(self.timestamp),
+            self.user,
+            if self.dryrun { "dry-run" } else { "run" },
+            self.removed.len(),
+            Bytes(self.size.disk).to_string(),
+            self.errors.len()
+        )
+    }
+
+    /// Details of the run, for `neaten history show <id>`.
+    pub fn details(&self) -> String {
+        let mut msg = format!("Run:          {}\n", self.id);
+        msg = format!("{}Date:         {}\n", msg, self::utc(self.timestamp));
+        msg = format!("{}User:         {}\n", msg, self.user);
+        msg = format!(
+            "{}Mode:         {}\n",
+            msg,
+            if self.dryrun { "dry-run" } else { "run" }
+        );
+        if let Some(config) = &self.config {
+            msg = format!("{}Config:       {}\n", msg, config.display());
+        }
+        if let Some(hash) = &self.config_hash {
+            msg = format!("{}Config hash:  {}\n", msg, hash);
+        }
+        for destination in &self.destinations {
+            msg = format!("{}Destination:  {}\n", msg, destination.display());
+        }
+        msg = format!(
+            "{}Freed:        {} ({} apparent)\n",
+            msg,
+            Bytes(self.size.disk),
+            Bytes(self.size.apparent)
+        );
+        if self.detached > 0 {
+            msg = format!(
+                "{}Detached:     {} folders, freed in background\n",
+                msg, self.detached
+            );
+        }
+
+        msg = format!("{}\nRemoved ({}):\n", msg, self.removed.len());
+        for removed in &self.removed {
+            msg = format!(
+                "{}  {:>10}  {}\n",
+                msg,
+                Bytes(removed.size.disk).to_string(),
+                removed.path.display()
+            );
+        }
+        if !self.errors.is_empty() {
+            msg = format!("{}\nErrors ({}):\n", msg, self.errors.len());
+            for error in &self.errors {
+                msg = format!("{}  {}\n", msg, error);
+            }
+        }
+        msg
+    }
+}
+
+/// Header of `Record::line`.
+pub fn header() -> String {
+    format!(
+        "{:>5}  {:<23}  {:<12}  {:<7}  {:>6}  {:>10}  {:>6}",
+        "ID", "Date", "User", "Mode", "Items", "Freed", "Errors"
+    )
+}
+
+/// Directory of the audit log.
+pub fn dir() -> crate::Result<PathBuf> {
+    let state = env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("LOCALAPPDATA").map(PathBuf::from))
+        .or_else(|| {
+            env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("state"))
+        })
+        .ok_or(AppError::new(
+            AppErrorKind::Functionality,
+            "can't find state directory for history, please set XDG_STATE_HOME",
+        ))?;
+
+    Ok(state.join("neaten"))
+}
+
+/// Append `record` to the audit log in `dir`, it gets the next id.
+pub fn append<P: AsRef<Path>>(dir: P, mut record: Record) -> crate::Result<Record> {
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir)?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .read(true)
+        .open(dir.join("history.jsonl"))?;
+
+    // concurrent runs must not get the same id
+    file.lock()?;
+    record.id = self::read(&file)?.last().map(|last| last.id).unwrap_or(0) + 1;
+    writeln!(file, "{}", serde_json::to_string(&record)?)?;
+    file.flush()?;
+
+    Ok(record)
+}
+
+/// Records of the audit log in `dir`, oldest first.
+pub fn load<P: AsRef<Path>>(dir: P) -> crate::Result<Vec<Record>> {
+    match File::open(dir.as_ref().join("history.jsonl")) {
+        Ok(file) => self::read(&file),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(vec![]),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn read(file: &File) -> crate::Result<Vec<Record>> {
+    let mut records = vec![];
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            records.push(serde_json::from_str(&line)?);
+        }
+    }
+    Ok(records)
+}
+
+fn hash(path: &Path) -> std::io::Result<String> {
+    Ok(format!("{:x}", Sha256::digest(fs::read(path)?)))
+}
+
+/// `YYYY-MM-DD hh:mm:ss UTC` of seconds since UNIX epoch.
+pub fn utc(timestamp: u64) -> String {
+    let days = (timestamp / 86400) as i64;
+    let seconds = timestamp % 86400;
+
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::EntryReport;
+
+    fn report() -> Report {
+        let mut report = Report::new(false);
+        let mut entry = EntryReport::new("/pool/rust");
+        entry.removed(
+            "/pool/rust/proj/target",
+            Size {
+                apparent: 10,
+                disk: 4096,
+            },
+        );
+        entry.error("permission denied");
+        report.entries.push(entry);
+        report
+    }
+
+    #[test]
+    fn append_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("config.json");
+        fs::write(&config, "[]").unwrap();
+
+        let first = append(dir.path(), Record::new(&report(), Some(&config))).unwrap();
+        let second = append(dir.path(), Record::new(&report(), None)).unwrap();
+        assert_eq!((first.id, second.id), (1, 2));
+
+        let records = load(dir.path()).unwrap();
+        assert_eq!(records, vec![first, second]);
+        assert_eq!(
+            records[0].config_hash.as_deref(),
+            Some("4f53cda18c2baa0c0354bb5f9a3ecbe5ed12ab4d8e11ba873c2f11161202b945")
+        );
+        assert_eq!(
+            records[0].removed,
+            vec![Removed {
+                path: PathBuf::from("/pool/rust/proj/target"),
+                size: Size {
+                    apparent: 10,
+                    disk: 4096
+                }
+            }]
+        );
+        assert_eq!(records[0].errors, vec![String::from("permission denied")]);
+    }
+
+    #[test]
+    fn empty_history() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(load(dir.path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn utc_date() {
+        assert_eq!(utc(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(utc(951782400), "2000-02-29 00:00:00 UTC");
+        assert_eq!(utc(1792394737), "2026-10-19 07:25:37 UTC");
+    }
+}
@@ -0,0 +1,35 @@
+//! Interruption of a run (Ctrl-C or `SIGTERM`).
+//!
+//! The first signal only asks the run to stop: items being removed are
+//! finished, the others are left for `neaten resume`. A second signal exits
+//! right away.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Catch interruptions, until then they stop the process as usual.
+#[cfg(unix)]
+pub fn install() {
+    extern "C" fn handle(_signal: libc::c_int) {
+        // only async-signal-safe calls in here
+        if REQUESTED.swap(true, Ordering::SeqCst) {
+            unsafe { libc::_exit(130) };
+        }
+    }
+
+    let handler = handle as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    unsafe {
+        libc::signal(libc::SIGINT, handler);
+        libc::signal(libc::SIGTERM, handler);
+    }
+}
+
+// signals are not available, an interruption stops the process as usual.
+#[cfg(not(unix))]
+pub fn install() {}
+
+/// Whether the run was asked to stop.
+pub fn requested() -> bool {
+    REQUESTED.load(Ordering::SeqCst)
+}
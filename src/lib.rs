@@ -1,10 +1,70 @@
+//! Clean up build artifacts and other disposable files.
+//!
+//! Besides the `neaten` command line, which [`Engine`] and [`Manager`] drive,
+//! the library can be embedded: a [`Scanner`] finds items to be removed in
+//! the destinations of [`Config`] entries, the resulting [`Plan`] can be
+//! inspected and filtered, then an [`Executor`] removes its items. Library
+//! calls print nothing, their outcome is returned as a [`Report`].
+//!
+//! ```no_run
+//! use neaten::{Config, Executor, Kind, Scanner};
+//! use std::time::{Duration, SystemTime};
+//!
+//! let configs = vec![Config::new("/usr/sample/rust", Kind::Folder, vec!["target"], None)];
+//! let (mut plan, _) = Scanner::new(&configs).scan();
+//!
+//! // only build folders untouched for a month
+//! let month_ago = SystemTime::now() - Duration::from_secs(30 * 24 * 60 * 60);
+//! plan.retain(|item| item.modified.is_some_and(|modified| modified < month_ago));
+//!
+//! let report = Executor::new().execute(&plan)?;
+//! println!("{} items removed, {} errors", report.removed(), report.errors());
+//! # Ok::<(), neaten::AppError>(())
+//! ```
+
+mod analysis;
+mod cache;
 mod config;
 mod engine;
 mod error;
+mod event;
+mod executor;
+mod explain;
+mod export;
+mod git;
+mod history;
+mod interrupt;
+mod lock;
+mod log;
 mod manager;
+pub mod matcher;
+mod ordered;
+mod plan;
+mod progress;
+mod remove;
+mod report;
+mod scanner;
+mod size;
+mod stats;
+mod theme;
+mod throttle;
+mod walker;
 
+pub use analysis::Sort;
 pub use config::{Config, Kind};
-pub use engine::Engine;
+pub use engine::{CacheCommand, Command, Engine, HistoryCommand};
+pub use error::AppError;
+pub use executor::Executor;
 pub use manager::Manager;
+pub use matcher::{Matcher, Verdict};
+pub use plan::{Entry, Item, Plan};
+pub use report::{EntryReport, Format, ItemReport, Report, Status};
+pub use scanner::Scanner;
+pub use size::Size;
+#[cfg(feature = "stats")]
+pub use stats::stats;
+pub use theme::Color;
+pub use throttle::Rate;
+pub use walker::{WalkEntry, WalkError, Walker};
 
 pub type Result<T> = std::result::Result<T, error::AppError>;
@@ -0,0 +1,281 @@
+use crate::{
+    error::{AppError, AppErrorKind},
+    history,
+};
+use std::{
+    collections::BTreeMap,
+    env,
+    fs::{self, DirBuilder, File, OpenOptions, TryLockError},
+    io::{Read, Seek, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+/// Advisory lock which prevents concurrent runs on the same destination.
+///
+/// The lock is released when it's dropped (or when the process dies), the
+/// lock file itself is kept so that waiting runs always lock the same file.
+#[derive(Debug)]
+pub struct Lock {
+    file: File,
+    mode: Mode,
+}
+
+/// How a folder is locked: a destination exclusively, folders around it
+/// shared, so runs on nested destinations exclude each other while runs on
+/// sibling ones don't.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Mode {
+    Shared,
+    Exclusive,
+}
+
+impl Lock {
+    /// Lock `file`, the lock file of `folder`, which is `destination` itself
+    /// or a folder around it.
+    fn open(
+        file: &Path,
+        folder: &Path,
+        destination: &Path,
+        mode: Mode,
+        wait: bool,
+    ) -> crate::Result<Lock> {
+        if let Some(dir) = file.parent() {
+            self::create_dir(dir)?;
+        }
+        let mut options = OpenOptions::new();
+        options.create(true).truncate(false).read(true).write(true);
+        // never write through a link planted in place of the lock file
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.custom_flags(libc::O_NOFOLLOW).mode(0o600);
+        }
+        let mut file = options.open(file)?;
+
+        let locked = match (mode, wait) {
+            (Mode::Shared, true) => file.lock_shared().map_err(TryLockError::Error),
+            (Mode::Shared, false) => file.try_lock_shared(),
+            (Mode::Exclusive, true) => file.lock().map_err(TryLockError::Error),
+            (Mode::Exclusive, false) => file.try_lock(),
+        };
+        match locked {
+            Ok(_) => {}
+            Err(TryLockError::WouldBlock) => {
+                // only a run locking it exclusively records its pid
+                let mut pid = String::new();
+                file.read_to_string(&mut pid)?;
+                let holder = match pid.trim() {
+                    "" => String::from("another neaten run"),
+                    pid => format!("another neaten run (pid {})", pid),
+                };
+                let locked = match mode {
+                    Mode::Exclusive => {
+                        format!("destination {:?} is locked by {}", destination, holder)
+                    }
+                    Mode::Shared => format!(
+                        "destination {:?} is inside {:?}, which is locked by {}",
+                        destination, folder, holder
+                    ),
+                };
+                return Err(AppError::new(
+                    AppErrorKind::Functionality,
+                    format!(
+                        "{}, use --wait to wait for it or --no-lock to skip locking",
+                        locked
+                    ),
+                ));
+            }
+            Err(TryLockError::Error(e)) => return Err(e.into()),
+        }
+
+        // record holder of the lock
+        if mode == Mode::Exclusive {
+            file.set_len(0)?;
+            file.rewind()?;
+            write!(file, "{}", process::id())?;
+            file.flush()?;
+        }
+
+        Ok(Lock { file, mode })
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        // runs sharing it later don't leave a pid behind
+        if self.mode == Mode::Exclusive {
+            let _ = self.file.set_len(0);
+        }
+    }
+}
+
+/// Lock each of `destinations`, and the folders around them, once. Locks are
+/// always taken in the same order, outer folders first, so that concurrent
+/// runs don't deadlock.
+pub fn acquire_all<'a, I>(destinations: I, wait: bool) -> crate::Result<Vec<Lock>>
+where
+    I: IntoIterator<Item = &'a PathBuf>,
+{
+    self::acquire_all_in(self::dir()?, destinations, wait)
+}
+
+fn acquire_all_in<'a, I>(dir: PathBuf, destinations: I, wait: bool) -> crate::Result<Vec<Lock>>
+where
+    I: IntoIterator<Item = &'a PathBuf>,
+{
+    // spellings of the same folder share their lock, which is exclusive if
+    // it's a destination of the run
+    let mut folders: BTreeMap<PathBuf, (Mode, &PathBuf)> = BTreeMap::new();
+    for destination in destinations {
+        let canonical = self::canonical(destination);
+        for (index, folder) in canonical.ancestors().enumerate() {
+            let mode = match index {
+                0 => Mode::Exclusive,
+                _ => Mode::Shared,
+            };
+            let locked = folders
+                .entry(folder.to_path_buf())
+                .or_insert((mode, destination));
+            if mode == Mode::Exclusive {
+                *locked = (mode, destination);
+            }
+        }
+    }
+
+    folders
+        .into_iter()
+        .map(|(folder, (mode, destination))| {
+            let file = self::file(&dir, &folder);
+            Lock::open(&file, &folder, destination, mode, wait)
+        })
+        .collect()
+}
+
+/// Directory of lock files, private to the user so that nobody else can
+/// hold, replace or redirect them. The cleaned tree is never touched.
+fn dir() -> crate::Result<PathBuf> {
+    match env::var_os("XDG_RUNTIME_DIR").filter(|dir| !dir.is_empty()) {
+        Some(runtime) => Ok(PathBuf::from(runtime).join("neaten")),
+        None => Ok(history::dir()?.join("locks")),
+    }
+}
+
+fn create_dir(dir: &Path) -> crate::Result<()> {
+    let mut builder = DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(dir)?;
+    Ok(())
+}
+
+/// Lock file of a destination in `dir`, named after its canonical path.
+#[cfg(test)]
+fn path<P: AsRef<Path>>(dir: P, destination: &Path) -> PathBuf {
+    self::file(dir.as_ref(), &self::canonical(destination))
+}
+
+fn canonical(destination: &Path) -> PathBuf {
+    fs::canonicalize(destination).unwrap_or(destination.to_path_buf())
+}
+
+/// Lock file of canonical path `folder` in `dir`.
+fn file(dir: &Path, folder: &Path) -> PathBuf {
+    // FNV-1a, stable between runs and versions
+    let hash = folder
+        .to_string_lossy()
+        .bytes()
+        .fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+
+    dir.join(format!("{:016x}.lock", hash))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_destination_same_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let locks = dir.path().join("locks");
+        assert_eq!(
+            path(&locks, dir.path()),
+            path(&locks, &dir.path().join("."))
+        );
+        assert_ne!(
+            path(&locks, dir.path()),
+            path(&locks, &dir.path().join("other"))
+        );
+    }
+
+    #[test]
+    fn locked_destination() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = path(dir.path().join("locks"), dir.path());
+
+        let lock = Lock::open(&file, dir.path(), dir.path(), Mode::Exclusive, false).unwrap();
+        let err = Lock::open(&file, dir.path(), dir.path(), Mode::Exclusive, false).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains(&format!("(pid {})", process::id()))
+        );
+
+        drop(lock);
+        assert!(Lock::open(&file, dir.path(), dir.path(), Mode::Exclusive, false).is_ok());
+    }
+
+    #[test]
+    fn lock_spellings_once() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("a")).unwrap();
+        let destinations = [dir.path().to_path_buf(), dir.path().join("a/..")];
+
+        let locks = acquire_all_in(dir.path().join("locks"), &destinations, false).unwrap();
+        let folders = fs::canonicalize(dir.path()).unwrap().ancestors().count();
+        assert_eq!(locks.len(), folders);
+    }
+
+    #[test]
+    fn nested_destinations() {
+        let dir = tempfile::tempdir().unwrap();
+        let locks = dir.path().join("locks");
+        let outer = dir.path().join("x");
+        let inner = outer.join("y");
+        let sibling = outer.join("z");
+        fs::create_dir_all(&inner).unwrap();
+        fs::create_dir_all(&sibling).unwrap();
+
+        let lock = acquire_all_in(locks.clone(), [&outer], false).unwrap();
+        let err = acquire_all_in(locks.clone(), [&inner], false).unwrap_err();
+        assert!(err.to_string().contains("locked by another neaten run"));
+        drop(lock);
+
+        let lock = acquire_all_in(locks.clone(), [&inner], false).unwrap();
+        assert!(acquire_all_in(locks.clone(), [&outer], false).is_err());
+        assert!(acquire_all_in(locks.clone(), [&sibling], false).is_ok());
+        drop(lock);
+
+        assert!(acquire_all_in(locks, [&outer], false).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn refuse_planted_link() {
+        let dir = tempfile::tempdir().unwrap();
+        let locks = dir.path().join("locks");
+        let file = path(&locks, dir.path());
+        let victim = dir.path().join("victim");
+        fs::write(&victim, "keep").unwrap();
+        fs::create_dir(&locks).unwrap();
+        std::os::unix::fs::symlink(&victim, &file).unwrap();
+
+        assert!(Lock::open(&file, dir.path(), dir.path(), Mode::Exclusive, false).is_err());
+        assert_eq!(fs::read_to_string(&victim).unwrap(), "keep");
+    }
+}
@@ -0,0 +1,112 @@
+//! Leveled human readable messages of a run.
+//!
+//! Messages go to stdout, unless stdout is reserved for machine readable
+//! output, then they go to stderr. Errors always go to stderr.
+//!
+//! Nothing is printed until output is enabled, so that the library API
+//! reports to its caller only.
+
+use crate::progress;
+use serde::Deserialize;
+use std::{
+    fmt::Display,
+    sync::atomic::{AtomicBool, AtomicU8, Ordering},
+};
+
+/// How much to print, each level includes the previous ones.
+#[derive(Deserialize, Default, Debug, PartialEq, PartialOrd, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    /// summary and errors only.
+    Quiet,
+    /// removed items and warnings.
+    #[default]
+    Normal,
+    /// excluded paths and items about to be removed.
+    Verbose,
+    /// traversal decisions, e.g. folders searched and items not matched.
+    Trace,
+}
+
+impl Level {
+    /// Level from `-q` and the number of `-v`.
+    pub fn new(quiet: bool, verbose: u8) -> Level {
+        match (quiet, verbose) {
+            (true, _) => Level::Quiet,
+            (false, 0) => Level::Normal,
+            (false, 1) => Level::Verbose,
+            _ => Level::Trace,
+        }
+    }
+}
+
+static OUTPUT: AtomicBool = AtomicBool::new(false);
+static STDERR: AtomicBool = AtomicBool::new(false);
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Normal as u8);
+
+/// Print messages at all.
+pub fn output(enabled: bool) {
+    OUTPUT.store(enabled, Ordering::Relaxed);
+}
+
+/// Send messages to stderr instead of stdout.
+pub fn to_stderr(enabled: bool) {
+    STDERR.store(enabled, Ordering::Relaxed);
+}
+
+pub fn set_level(level: Level) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+pub fn enabled(level: Level) -> bool {
+    OUTPUT.load(Ordering::Relaxed) && level as u8 <= LEVEL.load(Ordering::Relaxed)
+}
+
+pub fn print<T: Display>(level: Level, msg: T) {
+    if !self::enabled(level) {
+        return;
+    }
+
+    progress::clear();
+    if STDERR.load(Ordering::Relaxed) {
+        eprintln!("{}", msg);
+    } else {
+        println!("{}", msg);
+    }
+}
+
+pub fn info<T: Display>(msg: T) {
+    self::print(Level::Normal, msg);
+}
+
+pub fn verbose<T: Display>(msg: T) {
+    self::print(Level::Verbose, msg);
+}
+
+pub fn trace<T: Display>(msg: T) {
+    self::print(Level::Trace, msg);
+}
+
+pub fn error<T: Display>(msg: T) {
+    if !OUTPUT.load(Ordering::Relaxed) {
+        return;
+    }
+
+    progress::clear();
+    eprintln!("Error: {}", msg);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn level() {
+        assert_eq!(Level::new(true, 0), Level::Quiet);
+        assert_eq!(Level::new(false, 0), Level::Normal);
+        assert_eq!(Level::new(false, 1), Level::Verbose);
+        assert_eq!(Level::new(false, 2), Level::Trace);
+        assert_eq!(Level::new(false, 5), Level::Trace);
+        assert!(Level::Quiet < Level::Normal && Level::Verbose < Level::Trace);
+    }
+}
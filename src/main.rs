@@ -9,5 +9,5 @@ fn main() {
     // validate user input
     manager.validate(engine).unwrap_or_else(|err| err.exit());
     // execute
-    manager.execute().unwrap(); //.unwrap_or_else(|err| err.exit());
+    manager.execute().unwrap_or_else(|err| err.exit());
 }
@@ -1,17 +1,62 @@
 use crate::{
-    Config, Engine, Kind,
+    CacheCommand, Command, Config, Engine, HistoryCommand, Kind, Sort,
+    analysis::{Analysis, Artifact},
+    cache::{self, Cache},
     error::{AppError, AppErrorKind},
+    event,
+    executor::Executor,
+    explain::{Decision, Explanation},
+    export::{self, Document},
+    git,
+    history::{self, Record},
+    interrupt,
+    lock::{self, Lock},
+    log::{self, Level},
+    matcher::{self, Matcher, Verdict},
+    ordered,
+    plan::{Entry, Item, Plan},
+    progress, remove,
+    report::{EntryReport, Format, Report},
+    scanner,
+    size::{Bytes, Size},
+    stats, theme,
+    throttle::{self, Rate},
+    walker::{WalkEntry, Walker},
 };
+use rayon::{ThreadPoolBuilder, prelude::*};
 use serde::Deserialize;
+use sha2::{Digest, Sha256};
 use std::{
-    fs,
+    collections::HashSet,
+    env, fs,
+    io::{self, IsTerminal, Read, Write},
     path::{self, Path, PathBuf},
+    process::{self, Child, Stdio},
+    sync::{Mutex, OnceLock},
+    thread,
+    time::{Instant, SystemTime, UNIX_EPOCH},
 };
 
 #[derive(Deserialize, Debug, PartialEq)]
 pub struct Manager {
     configs: Vec<Config>,
     dryrun: bool,
+    force: bool,
+    jobs: usize,
+    no_cache: bool,
+    nice: bool,
+    rate_limit: Option<Rate>,
+    background: bool,
+    wait: bool,
+    no_lock: bool,
+    plan: Option<Plan>,
+    save_plan: Option<PathBuf>,
+    export: Option<PathBuf>,
+    format: Format,
+    level: Level,
+    config: Option<PathBuf>,
+    resume: Option<PathBuf>,
+    command: Option<Command>,
 }
 
 impl Manager {
@@ -19,12 +64,103 @@ impl Manager {
         Manager {
             configs: vec![],
             dryrun: false,
+            force: false,
+            jobs: 0,
+            no_cache: false,
+            nice: false,
+            rate_limit: None,
+            background: false,
+            wait: false,
+            no_lock: false,
+            plan: None,
+            save_plan: None,
+            export: None,
+            format: Format::Human,
+            level: Level::Normal,
+            config: None,
+            resume: None,
+            command: None,
         }
     }
 
-    pub fn validate(&mut self, engine: Engine) -> crate::Result<()> {
+    pub fn validate(&mut self, mut engine: Engine) -> crate::Result<()> {
+        // colors, before anything is printed
+        theme::init(engine.color, engine.format != Format::Human);
         // dryrun
         self.dryrun = engine.dryrun;
+        // force
+        self.force = engine.force;
+        // threads
+        self.jobs = engine.jobs;
+        // scan results of previous runs
+        self.no_cache = engine.no_cache;
+        // low priority
+        self.nice = engine.nice;
+        self.rate_limit = engine.rate_limit;
+        // removal in background
+        self.background = engine.background;
+        // lock
+        self.wait = engine.wait;
+        self.no_lock = engine.no_lock;
+        // one file system
+        let one_file_system = engine.one_file_system;
+        // depth, overriding the config when given
+        let (min_depth, max_depth) = (engine.min_depth, engine.max_depth);
+        // save plan
+        self.save_plan = engine.save_plan;
+        // report document, its type must be known before the run
+        if let Some(path) = &engine.report {
+            Document::from_path(path)?;
+        }
+        self.export = engine.report;
+        // output format
+        self.format = engine.format;
+        // verbosity
+        self.level = Level::new(engine.quiet, engine.verbose);
+
+        // past runs, cache, or removal of detached folders
+        if let Some(
+            command @ (Command::History { .. } | Command::Cache { .. } | Command::Purge { .. }),
+        ) = engine.command
+        {
+            self.command = Some(command);
+            return Ok(());
+        }
+
+        // analysis of destinations, or of a single path
+        if matches!(
+            engine.command,
+            Some(Command::Scan { .. } | Command::Explain { .. })
+        ) {
+            self.command = engine.command.take();
+        }
+
+        // apply a reviewed plan
+        if let Some(Command::Apply { plan: mut path }) = engine.command {
+            // check relative or absolute path
+            path = if path.is_relative() {
+                path::absolute(path)?
+            } else {
+                path
+            };
+
+            // plan file exists or not
+            if !path.exists() {
+                return Err(AppError::new(
+                    AppErrorKind::Usage,
+                    "plan file doesn't exists",
+                ));
+            }
+
+            self.plan = Some(Plan::load(&path)?);
+            self.config = Some(path);
+            return Ok(());
+        }
+
+        // items left by an interrupted run, of the destinations configured below
+        if matches!(engine.command, Some(Command::Resume)) {
+            self.command = engine.command.take();
+        }
 
         // config
         if let Some(mut path) = engine.config {
@@ -58,8 +194,13 @@ impl Manager {
             }
 
             // parse config file
-            self.parse(path)?;
-            Ok(())
+            self.parse(&path)?;
+            self.config = Some(path);
+            for config in self.configs.iter_mut() {
+                config.one_file_system |= one_file_system;
+                config.min_depth = min_depth.or(config.min_depth);
+                config.max_depth = max_depth.or(config.max_depth);
+            }
         } else {
             let destination = engine.destination.ok_or(AppError::new(
                 AppErrorKind::Usage,
@@ -92,32 +233,415 @@ impl Manager {
 
             // format user input
             self.format(destination, kind, patterns, engine.exclude)?;
-            Ok(())
+            if let Some(config) = self.configs.last_mut() {
+                config.one_file_system = one_file_system;
+                config.min_depth = min_depth;
+                config.max_depth = max_depth;
+            }
+        }
+
+        if self.command == Some(Command::Resume) {
+            self.load_resume(history::dir()?)?;
+        }
+        Ok(())
+    }
+
+    /// Plan left by the interrupted run of the configured destinations in
+    /// `dir`, refused when it removes anything outside of them.
+    fn load_resume(&mut self, dir: PathBuf) -> crate::Result<()> {
+        let destinations: Vec<&PathBuf> = self.configs.iter().map(|c| &c.destination).collect();
+        let path = self::resume_file(dir, &destinations);
+        if !path.exists() {
+            return Err(AppError::new(
+                AppErrorKind::Functionality,
+                "there is no interrupted run of these destinations to resume",
+            ));
+        }
+
+        let plan = Plan::load(&path)?;
+        let configured: HashSet<PathBuf> =
+            destinations.iter().map(|d| self::canonical(d)).collect();
+        if let Some(entry) = plan
+            .entries
+            .iter()
+            .find(|entry| !configured.contains(&self::canonical(&entry.destination)))
+        {
+            return Err(AppError::new(
+                AppErrorKind::Usage,
+                format!(
+                    "interrupted run cleaned {:?}, which is not a destination of the config",
+                    entry.destination
+                ),
+            ));
         }
+
+        self.plan = Some(plan);
+        self.resume = Some(path);
+        Ok(())
     }
 
     pub fn execute(&self) -> crate::Result<()> {
-        // loop over each config
-        for config in &self.configs {
-            helper::remove(
-                &config.destination,
-                &config.kind,
-                &config.patterns,
-                &config.exclude.clone().unwrap_or_default(),
-                self.dryrun,
+        // the library is silent, the command line prints
+        log::output(true);
+        // keep the system responsive, before threads are started
+        if self.nice
+            && let Err(e) = throttle::nice()
+        {
+            log::error(format!("can't lower priority: {}", e));
+        }
+        throttle::limit(self.rate_limit);
+
+        // threads to scan and remove with
+        let pool = ThreadPoolBuilder::new().num_threads(self.jobs).build()?;
+        pool.install(|| self.run())
+    }
+
+    fn run(&self) -> crate::Result<()> {
+        let start = Instant::now();
+        // keep stdout for machine readable output
+        log::to_stderr(self.format != Format::Human);
+        log::set_level(self.level);
+        // live counters, for people watching a terminal only
+        progress::enable(
+            self.format == Format::Human
+                && self.level != Level::Quiet
+                && io::stderr().is_terminal(),
+        );
+
+        // commands which don't remove anything
+        match &self.command {
+            Some(Command::History { action }) => return self.history(action.as_ref()),
+            Some(Command::Scan { sort, top }) => return self.analyze(*sort, *top),
+            Some(Command::Explain { path }) => return self.explain(path),
+            Some(Command::Cache { action }) => return self.cache(action),
+            Some(Command::Purge { root, device }) => {
+                let paths = helper::read_paths(io::stdin().lock())?;
+                return self.purge(root, *device, &paths);
+            }
+            _ => {}
+        }
+        event::enable(self.format == Format::Ndjson);
+
+        // prevent concurrent runs on the same destination, a run waiting for
+        // its locks is still stopped as usual
+        let _locks = self.lock()?;
+        // finish items being removed on Ctrl-C, and keep the others to resume
+        interrupt::install();
+
+        // reviewed plan or scan destinations
+        let mut report = Report::new(self.dryrun);
+        let plan = match &self.plan {
+            Some(plan) => {
+                report.entries = plan
+                    .entries
+                    .iter()
+                    .map(|entry| EntryReport::new(&entry.destination))
+                    .collect();
+                plan.clone()
+            }
+            None => {
+                let options = self.options();
+                let plan = self.scan(&mut report, &options);
+                if interrupt::requested() {
+                    progress::clear();
+                    return Err(AppError::new(
+                        AppErrorKind::Interrupted,
+                        "interrupted while scanning, nothing was removed",
+                    ));
+                }
+                self.save_cache(&options.cache);
+                plan
+            }
+        };
+
+        // save plan to review it before applying
+        if let Some(path) = &self.save_plan {
+            plan.save(path)?;
+        }
+
+        // loop over each entry, keeping items left by an interruption
+        let left = Executor::new()
+            .dryrun(self.dryrun)
+            .background(self.background)
+            .apply(&plan, &mut report);
+
+        progress::clear();
+        report.finish(start.elapsed());
+        log::print(Level::Quiet, report.summary());
+        if self.format == Format::Json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+
+        // shareable report
+        if let Some(path) = &self.export {
+            export::save(&report, path)?;
+        }
+
+        // audit log, the run itself is done even if it can't be recorded
+        let record = Record::new(&report, self.config.as_deref());
+        if let Err(e) = history::dir().and_then(|dir| history::append(dir, record)) {
+            log::error(format!("can't record run in history: {}", e.message()));
+        }
+
+        // remaining items, to continue with `neaten resume`
+        if interrupt::requested() {
+            let items: usize = left.len();
+            if !self.dryrun && items > 0 {
+                let dir = history::dir()?;
+                fs::create_dir_all(&dir)?;
+                left.save(self::resume_file(dir, &self.destinations()))?;
+                return Err(AppError::new(
+                    AppErrorKind::Interrupted,
+                    format!(
+                        "interrupted, {} items left, run `neaten resume` with the same destinations to continue",
+                        items
+                    ),
+                ));
+            }
+            return Err(AppError::new(AppErrorKind::Interrupted, "interrupted"));
+        }
+        self.resumed()?;
+
+        // failures were printed as they happened, scripts see them too
+        match report.errors() {
+            0 => Ok(()),
+            errors => Err(AppError::new(
+                AppErrorKind::Functionality,
+                format!("run finished with {} errors", errors),
+            )),
+        }
+    }
+
+    /// Forget the interrupted run once its items were all applied, a dry-run
+    /// leaves it to resume.
+    fn resumed(&self) -> crate::Result<()> {
+        if self.dryrun || self.command != Some(Command::Resume) {
+            return Ok(());
+        }
+        // plan of the interrupted run
+        if let Some(path) = &self.resume {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    fn analyze(&self, sort: Sort, top: usize) -> crate::Result<()> {
+        let mut report = Report::new(true);
+        let options = self.options();
+        let plan = self.scan(&mut report, &options);
+
+        let matchers: Vec<Box<dyn Matcher>> = self.configs.iter().map(Config::matcher).collect();
+        let artifacts: Vec<Artifact> = plan
+            .entries
+            .iter()
+            .zip(&matchers)
+            .flat_map(|(entry, matcher)| {
+                entry.items.iter().map(|item| {
+                    let pattern = WalkEntry::from_path(&item.path, 0)
+                        .map(|entry| matcher.check(&entry))
+                        .ok()
+                        .filter(Verdict::is_match)
+                        .map(|verdict| verdict.reason().to_string())
+                        .unwrap_or_default();
+                    self::measure(&item.path, item.modified, &pattern, &options.cache)
+                })
+            })
+            .collect();
+        if !interrupt::requested() {
+            self.save_cache(&options.cache);
+        }
+        let analysis = Analysis::new(artifacts, sort);
+        progress::clear();
+
+        if self.format == Format::Human {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default();
+            log::print(Level::Quiet, analysis.table(top, now));
+        } else {
+            println!("{}", serde_json::to_string_pretty(&analysis)?);
+        }
+        Ok(())
+    }
+
+    fn explain(&self, path: &Path) -> crate::Result<()> {
+        let path = path::absolute(path)?;
+        let options = helper::Options {
+            force: self.force,
+            ..Default::default()
+        };
+
+        let explanations: Vec<Explanation> = self
+            .configs
+            .iter()
+            .enumerate()
+            .map(|(index, config)| {
+                Explanation::new(
+                    index + 1,
+                    path::absolute(&config.destination).unwrap_or(config.destination.clone()),
+                    helper::explain(config, &path, &options),
+                )
+            })
+            .collect();
+
+        if self.format == Format::Human {
+            let mut msg = format!("Path: {}", path.display());
+            for explanation in &explanations {
+                msg = format!("{}\n{}", msg, explanation);
+            }
+            log::print(Level::Quiet, msg);
+        } else {
+            println!("{}", serde_json::to_string_pretty(&explanations)?);
+        }
+        Ok(())
+    }
+
+    /// Remove folders detached by a run in background, others are refused.
+    fn purge(&self, root: &Path, device: Option<u64>, paths: &[PathBuf]) -> crate::Result<()> {
+        // errors end up in the purge log, dated as nobody watches them
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        let date = history::utc(now);
+        let (purged, size) = paths
+            .par_iter()
+            .filter_map(|path| {
+                let name = path.file_name().and_then(|name| name.to_str());
+                if !name.is_some_and(remove::is_trash) {
+                    log::error(format!(
+                        "{}: refusing to purge {:?}, it wasn't detached",
+                        date, path
+                    ));
+                    return None;
+                }
+                match helper::remove_item(root, path, device) {
+                    Ok(size) => Some((1, size)),
+                    Err(e) => {
+                        log::error(format!("{}: can't purge {:?}: {}", date, path, e));
+                        None
+                    }
+                }
+            })
+            .reduce(
+                || (0, Size::default()),
+                |(count, size), (other, other_size)| (count + other, size + other_size),
+            );
+        // detached folders are measured while they are removed
+        if purged > 0 {
+            log::print(
+                Level::Quiet,
+                format!(
+                    "{}: freed {} ({} apparent) from {} detached folders of {:?}",
+                    date,
+                    Bytes(size.disk),
+                    Bytes(size.apparent),
+                    purged,
+                    root
+                ),
             );
+        }
+        Ok(())
+    }
+
+    fn cache(&self, action: &CacheCommand) -> crate::Result<()> {
+        match action {
+            CacheCommand::Clear => {
+                let count = cache::clear(cache::dir()?)?;
+                log::print(Level::Normal, format!("Removed {} cache files", count));
+            }
+        }
+        Ok(())
+    }
+
+    fn history(&self, action: Option<&HistoryCommand>) -> crate::Result<()> {
+        let records = history::load(history::dir()?)?;
 
-            // let mut item = helper::Remove {
-            //     destination: config.destination.clone(),
-            //     kind: config.kind.clone(),
-            //     patterns: config.patterns.clone(),
-            //     dryrun: self.dryrun,
-            // };
-            // helper::remove_as_mut(&mut item);
+        match action {
+            None => {
+                if self.format == Format::Human {
+                    println!("{}", history::header());
+                    records
+                        .iter()
+                        .for_each(|record| println!("{}", record.line()));
+                } else {
+                    println!("{}", serde_json::to_string_pretty(&records)?);
+                }
+            }
+            Some(HistoryCommand::Show { id }) => {
+                let record =
+                    records
+                        .iter()
+                        .find(|record| record.id == *id)
+                        .ok_or(AppError::new(
+                            AppErrorKind::Functionality,
+                            format!("run {} doesn't exists", id),
+                        ))?;
+                if self.format == Format::Human {
+                    print!("{}", record.details());
+                } else {
+                    println!("{}", serde_json::to_string_pretty(record)?);
+                }
+            }
         }
         Ok(())
     }
 
+    /// Options of a scan, with results of the previous one unless `--no-cache`.
+    fn options(&self) -> helper::Options {
+        let cache = match self.no_cache {
+            true => Cache::default(),
+            false => match cache::dir() {
+                Ok(dir) => Cache::load(dir, &self.configs),
+                Err(e) => {
+                    log::verbose(format!("Scanning without cache: {}", e.message()));
+                    Cache::default()
+                }
+            },
+        };
+        helper::Options {
+            force: self.force,
+            cache,
+        }
+    }
+
+    /// Keep results of a scan for the next one, a scan is done even if they
+    /// can't be saved.
+    fn save_cache(&self, cache: &Cache) {
+        if let Err(e) = cache.save() {
+            log::error(format!("can't save scan cache: {}", e.message()));
+        }
+    }
+
+    fn scan(&self, report: &mut Report, options: &helper::Options) -> Plan {
+        scanner::plan(&self.configs, options, report)
+    }
+
+    fn lock(&self) -> crate::Result<Vec<Lock>> {
+        if self.dryrun || self.no_lock {
+            return Ok(vec![]);
+        }
+
+        lock::acquire_all(self.destinations(), self.wait)
+    }
+
+    /// Destinations of the run, of the reviewed plan or of the configs.
+    fn destinations(&self) -> Vec<&PathBuf> {
+        match &self.plan {
+            Some(plan) => plan
+                .entries
+                .iter()
+                .map(|entry| &entry.destination)
+                .collect(),
+            None => self
+                .configs
+                .iter()
+                .map(|config| &config.destination)
+                .collect(),
+        }
+    }
+
     fn add(&mut self, config: Config) {
         self.configs.push(config);
     }
@@ -140,13 +664,55 @@ impl Manager {
     }
 }
 
+/// Matched item of `neaten scan`, a file is measured again only if it was
+/// modified since the previous scan. A folder is always measured, its own
+/// modification time doesn't tell whether its content grew.
+fn measure(path: &Path, modified: Option<SystemTime>, pattern: &str, cache: &Cache) -> Artifact {
+    let Some(modified) = modified.filter(|_| !path.is_dir()) else {
+        return Artifact::new(path, pattern);
+    };
+    if let Some(measure) = cache.size(path, modified) {
+        return Artifact::measured(path, pattern, measure.size, measure.newest);
+    }
+
+    let artifact = Artifact::new(path, pattern);
+    cache.insert_size(
+        path.to_path_buf(),
+        modified,
+        artifact.size,
+        artifact.modified,
+    );
+    artifact
+}
+
+/// Plan of the items left by an interrupted run of `destinations` in `dir`,
+/// named after their canonical paths so that runs of other destinations
+/// don't overwrite it.
+fn resume_file(dir: PathBuf, destinations: &[&PathBuf]) -> PathBuf {
+    let mut paths: Vec<PathBuf> = destinations.iter().map(|d| self::canonical(d)).collect();
+    paths.sort();
+    paths.dedup();
+
+    let mut hasher = Sha256::new();
+    for path in paths {
+        hasher.update(path.as_os_str().as_encoded_bytes());
+        hasher.update([0]);
+    }
+    let hash = format!("{:x}", hasher.finalize());
+    dir.join(format!("resume-{}.json", &hash[..16]))
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or(path.to_path_buf())
+}
+
 impl Default for Manager {
     fn default() -> Self {
         Self::new()
     }
 }
 
-mod helper {
+pub(crate) mod helper {
     use super::*;
 
     #[allow(dead_code)]
@@ -156,6 +722,7 @@ mod helper {
         pub patterns: Vec<String>,
         pub exclude: Vec<String>,
         pub dryrun: bool,
+        pub force: bool,
     }
 
     impl AsMut<Remove> for Remove {
@@ -167,29 +734,44 @@ mod helper {
     #[allow(dead_code)]
     pub fn remove_as_mut<T: AsMut<Remove>>(item: &mut T) {
         let item = item.as_mut();
+        let mut report = EntryReport::new(&item.destination);
 
         if item.destination.exists() {
-            // get child item of kind
-            let children = self::childern(&item.destination, &item.exclude);
-
-            // iterate over each child
-            for child in &children {
-                // if match, then remove
-                match self::pattern_check(child, &item.patterns, &item.kind) {
-                    Some(_) => {
-                        // remove child
-                        println!("Removing {:?}...", child);
-                        if !&item.dryrun {
-                            match self::remove_item(child) {
-                                Ok(_) => println!("Removed {:?}...", child),
-                                Err(e) => eprintln!("Error: {}", e),
-                            }
-                        }
+            let matcher = matcher::patterns(&item.kind, &item.patterns);
+            let mut walker = Walker::new(&item.destination);
+            while let Some(entry) = walker.next() {
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        report.error(e);
+                        continue;
+                    }
+                };
+                let path = entry.path();
+
+                // don't search paths that exist in exclude list
+                let name = entry.file_name().to_str().unwrap_or_default();
+                if self::find(name, &item.exclude).is_some() {
+                    report.exclude(path);
+                    walker.skip_current_dir();
+                    continue;
+                }
+
+                // if match, then remove, else its content is walked
+                if matcher.check(&entry).is_match() {
+                    walker.skip_current_dir();
+
+                    // keep content managed by git
+                    if !item.force && self::is_protected(path, &mut report) {
+                        continue;
                     }
-                    None => {
-                        if child.is_dir() {
-                            item.destination = child.to_path_buf();
-                            self::remove_as_mut(item);
+
+                    // remove child
+                    log::verbose(format!("Removing {:?}...", path));
+                    if !&item.dryrun {
+                        match self::remove_item(&item.destination, path, None) {
+                            Ok(_) => log::info(format!("Removed {:?}...", path)),
+                            Err(e) => log::error(e),
                         }
                     }
                 }
@@ -197,142 +779,842 @@ mod helper {
         }
     }
 
-    // TODO: think remove need to return Result<...>?
-    pub fn remove<P: AsRef<Path>>(
-        destination: P,
-        kind: &Kind,
-        patterns: &[String],
-        exclude: &[String],
-        dryrun: bool,
-    ) {
-        // pub fn remove(destination: &Path, kind: &Kind, patterns: &[String], dryrun: bool) {
-        let destination = destination.as_ref();
-        if destination.exists() {
-            // get child item of kind
-            let children = self::childern(destination, exclude);
-
-            // iterate over each child
-            for child in &children {
-                // if match, then remove
-                match self::pattern_check(child, patterns, kind) {
-                    Some(_) => {
-                        // remove child
-                        println!("\u{1b}[91mRemoving\u{1b}[0m {:?}...", child);
-                        if !dryrun {
-                            match self::remove_item(child) {
-                                Ok(_) => println!("\u{1b}[31mRemoved\u{1b}[0m {:?}...", child),
-                                Err(e) => eprintln!("Error: {}", e),
-                            }
-                        }
-                    }
-                    None => {
-                        if child.is_dir() {
-                            self::remove(child, kind, patterns, exclude, dryrun);
-                        }
+    /// Options shared by every config entry of a run.
+    #[derive(Default)]
+    pub struct Options {
+        pub force: bool,
+        /// results of the previous scan.
+        pub cache: Cache,
+    }
+
+    /// Scan destination of `config` for items to be removed.
+    #[cfg(test)]
+    pub fn scan(config: &Config, options: &Options, report: &mut EntryReport) -> Entry {
+        self::scan_all(
+            std::slice::from_ref(config),
+            options,
+            std::slice::from_mut(report),
+        )
+        .remove(0)
+    }
+
+    /// Scan destinations of `configs` for items to be removed, one entry and
+    /// report per config. Configs whose destinations overlap (the same one, or
+    /// one inside another) are scanned in a single walk of the tree.
+    pub fn scan_all(
+        configs: &[Config],
+        options: &Options,
+        reports: &mut [EntryReport],
+    ) -> Vec<Entry> {
+        let scopes: Vec<Scope> = configs
+            .iter()
+            .enumerate()
+            .map(|(index, config)| Scope::new(index, config))
+            .collect();
+
+        let mut entries: Vec<Entry> = scopes
+            .iter()
+            .zip(reports.iter_mut())
+            .map(|(scope, report)| {
+                report.destination = scope.destination.clone();
+                report.scan_started();
+                Entry {
+                    destination: scope.destination.clone(),
+                    one_file_system: scope.config.one_file_system,
+                    items: vec![],
+                }
+            })
+            .collect();
+
+        // findings are reported as soon as earlier ones in traversal order are,
+        // while the walk goes on
+        let (stream, findings): (Findings, _) = ordered::channel();
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                for (index, found) in findings.flatten() {
+                    self::report(found, &mut entries[index].items, &mut reports[index]);
+                }
+            });
+
+            let groups = self::groups(scopes.iter().collect());
+            let streams: Vec<Findings> = groups.iter().map(|_| stream.open()).collect();
+            drop(stream);
+            groups
+                .par_iter()
+                .zip(streams)
+                .for_each(|(group, stream)| self::scan_group(group, options, &stream));
+        });
+        entries
+    }
+
+    /// Config entry taking part in a walk.
+    struct Scope<'a> {
+        /// position of the config.
+        index: usize,
+        destination: PathBuf,
+        /// number of components of destination, to tell depth of its content.
+        components: usize,
+        config: &'a Config,
+        /// matcher of config patterns and rules, built once per scan.
+        matcher: Box<dyn Matcher>,
+        device: Option<u64>,
+    }
+
+    /// What a scan does with an entry it reaches, for one config entry.
+    enum Step {
+        /// detached folder, never searched, reclaimed if its owner is gone.
+        Trash {
+            stale: bool,
+        },
+        /// excluded by `exclude[index]` of the config.
+        Excluded(usize),
+        OtherFileSystem,
+        /// matches patterns or rules, for the given reason.
+        Matched(String),
+        /// doesn't match, and is searched.
+        Search,
+        /// doesn't match, and isn't searched: a file, a symbolic link or a
+        /// folder at max depth.
+        Leaf,
+    }
+
+    impl<'a> Scope<'a> {
+        fn new(index: usize, config: &'a Config) -> Scope<'a> {
+            // plan must not depend on the working directory
+            let destination =
+                path::absolute(&config.destination).unwrap_or(config.destination.clone());
+            // device of destination, to stay on the same file system
+            let device = if config.one_file_system {
+                self::device(&destination)
+            } else {
+                None
+            };
+            Scope {
+                index,
+                components: destination.components().count(),
+                destination,
+                config,
+                matcher: config.matcher(),
+                device,
+            }
+        }
+
+        /// Decision on `entry`, which lives under destination. Both scans and
+        /// `explain` follow it.
+        fn step(&self, entry: &WalkEntry) -> Step {
+            let path = entry.path();
+            let name = entry.file_name().to_str().unwrap_or_default();
+            if remove::is_trash(name) {
+                let stale = remove::is_stale(name);
+                return Step::Trash { stale };
+            }
+            // don't search paths that exist in exclude list
+            let exclude = self.config.exclude.as_deref().unwrap_or_default();
+            if let Some(index) = self::find(name, exclude) {
+                return Step::Excluded(index);
+            }
+            // don't cross mount points
+            if self.device.is_some() && self::device(path) != self.device {
+                return Step::OtherFileSystem;
+            }
+
+            let depth = path.components().count() - self.components;
+            if depth >= self.config.min_depth.unwrap_or_default()
+                && let Verdict::Match(reason) = self.matcher.check(entry)
+            {
+                return Step::Matched(reason);
+            }
+            // never descend into a symbolic link, nor below max depth
+            let max_depth = self.config.max_depth.unwrap_or(usize::MAX);
+            if !entry.is_symlink() && entry.is_dir() && depth < max_depth {
+                Step::Search
+            } else {
+                Step::Leaf
+            }
+        }
+    }
+
+    /// What a scan found, reported in traversal order, so the report doesn't
+    /// depend on how threads were scheduled.
+    pub enum Found {
+        Excluded(PathBuf),
+        Skipped(PathBuf, String),
+        Matched(Item),
+        Error(String),
+        Trace(String),
+    }
+
+    /// Findings of a walk, by position of their config entry.
+    type Findings = ordered::Sender<Vec<(usize, Found)>>;
+
+    /// Group overlapping destinations under the outermost one.
+    fn groups<'a>(mut scopes: Vec<&'a Scope<'a>>) -> Vec<Vec<&'a Scope<'a>>> {
+        scopes.sort_by(|a, b| a.destination.cmp(&b.destination));
+        let mut groups: Vec<Vec<&Scope>> = vec![];
+        for scope in scopes {
+            match groups.last_mut() {
+                Some(group) if scope.destination.starts_with(&group[0].destination) => {
+                    group.push(scope)
+                }
+                _ => groups.push(vec![scope]),
+            }
+        }
+        groups
+    }
+
+    /// Scan destination of the first entry of `group` for all its entries,
+    /// the others have their destination inside it.
+    fn scan_group(group: &[&Scope], options: &Options, stream: &Findings) {
+        let root = &group[0].destination;
+        stats::stat();
+        if !root.exists() {
+            return;
+        }
+        let (active, pending): (Vec<&Scope>, Vec<&Scope>) =
+            group.iter().partition(|scope| &scope.destination == root);
+
+        // every folder is searched by its own task, at any depth, so a single
+        // wide folder is shared by threads too
+        let reached = Mutex::new(vec![]);
+        let search = Search {
+            options,
+            reached: &reached,
+        };
+        let (root, found) = (root.clone(), stream.open());
+        rayon::scope(|tasks| search.dir(tasks, root, active, pending.clone(), found));
+
+        // destinations which can't be walked to, e.g. behind a symbolic link or
+        // a folder which can't be listed, are scanned on their own
+        let reached = reached.into_inner().unwrap_or_default();
+        let unreached: Vec<&Scope> = pending
+            .into_iter()
+            .filter(|scope| !reached.contains(&scope.index))
+            .collect();
+        for group in self::groups(unreached) {
+            self::scan_group(&group, options, &stream.open());
+        }
+    }
+
+    /// Search shared by the tasks of a walk.
+    #[derive(Clone, Copy)]
+    struct Search<'s> {
+        options: &'s Options,
+        /// positions of pending entries whose destination was reached.
+        reached: &'s Mutex<Vec<usize>>,
+    }
+
+    impl<'s> Search<'s> {
+        /// Search content of `dir` for `active` entries, which search it, on the
+        /// way to `pending` entries, whose destination is further inside. Its
+        /// sub-folders are searched by other `tasks`, each one reporting in a
+        /// stream opened at its place in `stream`.
+        fn dir<'a: 's>(
+            self,
+            tasks: &rayon::Scope<'s>,
+            dir: PathBuf,
+            active: Vec<&'a Scope<'a>>,
+            pending: Vec<&'a Scope<'a>>,
+            stream: Findings,
+        ) {
+            if interrupt::requested() {
+                return;
+            }
+            progress::scanned();
+            let mut walker = Walker::new(&dir).max_depth(1);
+            let mut frame = Frame::open(&dir, active, pending, &mut walker, &self.options.cache);
+
+            for entry in walker {
+                if interrupt::requested() {
+                    // folder is incomplete, it's not cached
+                    return;
+                }
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        frame.listing = None;
+                        stream.send(
+                            frame
+                                .active
+                                .iter()
+                                .map(|scope| (scope.index, Found::Error(e.to_string())))
+                                .collect(),
+                        );
+                        continue;
                     }
+                };
+
+                let mut found = vec![];
+                let mut reached = vec![];
+                let (searching, pending) = self::visit(
+                    &entry,
+                    &frame.active,
+                    &frame.pending,
+                    self.options,
+                    &mut found,
+                    &mut reached,
+                );
+                frame.add(&entry, &found);
+                if !found.is_empty() {
+                    stream.send(found);
+                }
+                if !reached.is_empty()
+                    && let Ok(mut all) = self.reached.lock()
+                {
+                    all.extend(reached);
+                }
+
+                if entry.file_type().is_dir() && (!searching.is_empty() || !pending.is_empty()) {
+                    let stream = stream.open();
+                    let dir = entry.into_path();
+                    tasks.spawn(move |tasks| self.dir(tasks, dir, searching, pending, stream));
                 }
             }
+            frame.close(&self.options.cache);
         }
     }
 
-    // TODO: return Result<Vec<PathBuf>, AppError>
-    pub fn childern<P: AsRef<Path>>(parent: P, exclude: &[String]) -> Vec<PathBuf> {
-        let mut children = Vec::new();
-
-        match fs::read_dir(parent) {
-            Ok(entries) => {
-                for entry in entries {
-                    match entry {
-                        Ok(entry) => {
-                            // don't add path that exists in exclude list
-                            let path = entry.path();
-                            let name = path
-                                .file_name()
-                                .unwrap_or_default()
-                                .to_str()
-                                .unwrap_or_default();
-                            match self::find(name, exclude) {
-                                Some(_) => {
-                                    // println!("index: {:?}", index);
-                                    // println!("path: {:?}", name);
-                                    // println!("exclude: {:?}", exclude);
-
-                                    println!("\u{1b}[33mExclude\u{1b}[0m {:?}...", path);
-                                }
-                                None => children.push(path),
-                            }
-
-                            // check child is matching with patterns or not
-                            // if *kind == Kind::Folder && entry.file_type().unwrap().is_dir() {
-                            //     children.push(entry.path());
-                            // } else if *kind == Kind::File && entry.file_type().unwrap().is_file() {
-                            //     children.push(entry.path());
-                            // }
-                        }
-                        Err(e) => {
-                            eprintln!("Error reading directory entry: {}", e);
-                        }
+    /// Folder being searched by `active` entries, on the way to `pending` ones.
+    struct Frame<'a> {
+        active: Vec<&'a Scope<'a>>,
+        pending: Vec<&'a Scope<'a>>,
+        /// folder, its modification time and sub-folders, to cache once it's
+        /// searched, dropped as soon as anything is found in it.
+        listing: Option<(PathBuf, SystemTime, Vec<String>)>,
+    }
+
+    impl<'a> Frame<'a> {
+        /// Start searching `dir`, the root of `walker`. If it wasn't modified
+        /// since it was cached, its cached sub-folders are walked instead of
+        /// reading it.
+        fn open(
+            dir: &Path,
+            active: Vec<&'a Scope<'a>>,
+            pending: Vec<&'a Scope<'a>>,
+            walker: &mut Walker,
+            cache: &Cache,
+        ) -> Frame<'a> {
+            let modified = match cache.enabled() {
+                true => {
+                    stats::stat();
+                    fs::symlink_metadata(dir).and_then(|metadata| metadata.modified())
+                }
+                false => Err(io::ErrorKind::Unsupported.into()),
+            };
+            let Ok(modified) = modified else {
+                return Frame {
+                    active,
+                    pending,
+                    listing: None,
+                };
+            };
+
+            if let Some(dirs) = cache.dirs(dir, modified) {
+                let entries: io::Result<Vec<WalkEntry>> = dirs
+                    .iter()
+                    .map(|name| WalkEntry::from_path(dir.join(name), 0))
+                    .collect();
+                // read it anyway if a sub-folder changed since
+                if let Ok(entries) = entries
+                    && entries.iter().all(|entry| entry.file_type().is_dir())
+                {
+                    walker.listing(entries);
+                }
+            }
+            Frame {
+                active,
+                pending,
+                listing: Some((dir.to_path_buf(), modified, vec![])),
+            }
+        }
+
+        /// Account `entry` of the folder, with what was `found` for it.
+        fn add(&mut self, entry: &WalkEntry, found: &[(usize, Found)]) {
+            let Some((_, _, dirs)) = &mut self.listing else {
+                return;
+            };
+            let file_type = entry.file_type();
+            let name = entry.file_name().to_str();
+            match name {
+                Some(name) if file_type.is_dir() => dirs.push(name.to_string()),
+                Some(_) if file_type.is_file() => {}
+                // a symbolic link may point to anything next time
+                _ => self.listing = None,
+            }
+            if found
+                .iter()
+                .any(|(_, found)| !matches!(found, Found::Trace(_)))
+            {
+                self.listing = None;
+            }
+        }
+
+        /// Cache the folder, once all its entries were searched.
+        fn close(self, cache: &Cache) {
+            if let Some((dir, modified, dirs)) = self.listing {
+                cache.insert_dirs(dir, modified, dirs);
+            }
+        }
+    }
+
+    /// Evaluate `entry` for `active` entries, return the entries which search
+    /// inside it, and the `pending` ones whose destination is further inside.
+    fn visit<'a>(
+        entry: &WalkEntry,
+        active: &[&'a Scope<'a>],
+        pending: &[&'a Scope<'a>],
+        options: &Options,
+        found: &mut Vec<(usize, Found)>,
+        reached: &mut Vec<usize>,
+    ) -> (Vec<&'a Scope<'a>>, Vec<&'a Scope<'a>>) {
+        let path = entry.path();
+        let mut searching = vec![];
+        // asked once, whatever the number of entries matching it
+        let protection = OnceLock::new();
+        // messages nobody reads are not built
+        let tracing = log::enabled(Level::Trace);
+
+        for scope in active {
+            match scope.step(entry) {
+                // detached folder, being removed by another run, unless that
+                // run is gone, then it's reclaimed by the first entry here
+                Step::Trash { stale } => {
+                    if stale {
+                        self::plan_item(scope, path, found);
+                    }
+                    return (vec![], vec![]);
+                }
+                Step::Excluded(_) => found.push((scope.index, Found::Excluded(path.to_path_buf()))),
+                Step::OtherFileSystem => {
+                    let reason = String::from("on another file system");
+                    found.push((scope.index, Found::Skipped(path.to_path_buf(), reason)));
+                }
+                Step::Matched(_) => {
+                    // keep content managed by git
+                    if !options.force
+                        && let Some(reason) = protection.get_or_init(|| self::protection(path))
+                    {
+                        found.push((
+                            scope.index,
+                            Found::Skipped(path.to_path_buf(), reason.clone()),
+                        ));
+                        continue;
+                    }
+                    self::plan_item(scope, path, found);
+                }
+                Step::Search => {
+                    if tracing {
+                        let msg = format!("Search {:?}...", path);
+                        found.push((scope.index, Found::Trace(msg)));
+                    }
+                    searching.push(*scope);
+                }
+                Step::Leaf => {
+                    if tracing {
+                        let msg = format!("No match {:?}...", path);
+                        found.push((scope.index, Found::Trace(msg)));
                     }
                 }
             }
+        }
+
+        // entries whose destination is the entry start searching it
+        let (starting, pending): (Vec<&Scope>, Vec<&Scope>) = pending
+            .iter()
+            .filter(|scope| scope.destination.starts_with(path))
+            .partition(|scope| scope.destination == path);
+        if entry.file_type().is_dir() {
+            reached.extend(starting.iter().map(|scope| scope.index));
+            searching.extend(starting);
+        }
+        (searching, pending)
+    }
+
+    /// Plan to remove `path` for `scope`.
+    fn plan_item(scope: &Scope, path: &Path, found: &mut Vec<(usize, Found)>) {
+        match Item::new(path) {
+            Ok(item) => {
+                progress::matched();
+                found.push((scope.index, Found::Matched(item)));
+            }
+            Err(e) => found.push((scope.index, Found::Error(e.to_string()))),
+        }
+    }
+
+    /// Add a finding of a scan to `report`, a matched item to `items`.
+    fn report(found: Found, items: &mut Vec<Item>, report: &mut EntryReport) {
+        match found {
+            Found::Excluded(path) => report.exclude(path),
+            Found::Skipped(path, reason) => report.skip(path, reason),
+            Found::Matched(item) => {
+                report.matched(&item.path);
+                items.push(item);
+            }
+            Found::Error(e) => report.error(e),
+            Found::Trace(msg) => log::trace(msg),
+        }
+    }
+
+    /// Remove planned items of `entry`, with `background` folders are detached
+    /// and removed by another process once the run is over. Return the items
+    /// left when the run is interrupted.
+    pub fn apply(
+        entry: &Entry,
+        dryrun: bool,
+        background: bool,
+        report: &mut EntryReport,
+    ) -> Vec<Item> {
+        // device of destination, to stay on the same file system
+        let device = if entry.one_file_system {
+            self::device(&entry.destination)
+        } else {
+            None
+        };
+
+        // started first, so that folders are detached under its name
+        let purger = match background && !dryrun {
+            true => self::spawn_purge(&entry.destination, device),
+            false => None,
+        };
+
+        // remove items in parallel, planned items are never nested, and report
+        // them in plan order as soon as earlier ones are
+        let (stream, outcomes) = ordered::channel();
+        let owner = purger.as_ref().map(Child::id);
+        let mut left = vec![];
+        let mut detached: Vec<PathBuf> = vec![];
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                for (item, outcome) in outcomes {
+                    self::report_outcome(item, outcome, report, &mut left, &mut detached);
+                }
+            });
+
+            let streams: Vec<_> = entry.items.iter().map(|_| stream.open()).collect();
+            drop(stream);
+            entry
+                .items
+                .par_iter()
+                .zip(streams)
+                .for_each(|(item, stream)| {
+                    let outcome = self::remove_planned(entry, item, dryrun, owner, device);
+                    stream.send((item, outcome));
+                });
+        });
+
+        if let Some(purger) = purger {
+            self::hand_over(purger, &detached);
+        }
+        left
+    }
+
+    /// Remove planned `item` of `entry`, unless it changed since planning. A
+    /// folder is detached instead when there is an `owner` to remove it.
+    fn remove_planned(
+        entry: &Entry,
+        item: &Item,
+        dryrun: bool,
+        owner: Option<u32>,
+        device: Option<u64>,
+    ) -> Outcome {
+        // items being removed are finished, others are left
+        if interrupt::requested() {
+            return Outcome::Interrupted;
+        }
+        if item.changed() {
+            return Outcome::Changed;
+        }
+
+        // removal measures what it frees, only dry-runs measure beforehand
+        if dryrun {
+            let size = Size::of(&item.path);
+            progress::queued(size);
+            return Outcome::WouldRemove(size);
+        }
+        let removed = match owner {
+            Some(owner) => remove::detach(&entry.destination, &item.path, device, owner),
+            None => self::remove_item(&entry.destination, &item.path, device)
+                .map(remove::Detached::Removed),
+        };
+        match removed {
+            Ok(remove::Detached::Removed(size)) => Outcome::Removed(size),
+            Ok(remove::Detached::Renamed(trash)) => Outcome::Detached(trash),
+            Err(e) => Outcome::Failed(e),
+        }
+    }
+
+    /// Add `outcome` of `item` to `report`, keeping the item in `left` when
+    /// the run was interrupted, and its detached folder in `detached`.
+    fn report_outcome(
+        item: &Item,
+        outcome: Outcome,
+        report: &mut EntryReport,
+        left: &mut Vec<Item>,
+        detached: &mut Vec<PathBuf>,
+    ) {
+        match outcome {
+            Outcome::Interrupted => {
+                report.skip(&item.path, "interrupted");
+                left.push(item.clone());
+            }
+            Outcome::Changed => report.skip(&item.path, "changed since it was planned"),
+            Outcome::WouldRemove(size) => report.would_remove(&item.path, size),
+            Outcome::Removed(size) => {
+                report.removing(&item.path);
+                report.removed(&item.path, size);
+            }
+            Outcome::Detached(trash) => {
+                report.removing(&item.path);
+                report.detached(&item.path);
+                detached.push(trash);
+            }
+            Outcome::Failed(e) => {
+                report.removing(&item.path);
+                report.failed(&item.path, e);
+            }
+        }
+    }
+
+    /// What happened to a planned item.
+    enum Outcome {
+        Interrupted,
+        Changed,
+        WouldRemove(Size),
+        Removed(Size),
+        /// renamed aside to the given path, measured once it's removed.
+        Detached(PathBuf),
+        Failed(std::io::Error),
+    }
+
+    /// Start the process removing detached folders in background, which
+    /// gets their paths once they are all detached. `None` if it can't be
+    /// started, items are removed right away then.
+    fn spawn_purge(root: &Path, device: Option<u64>) -> Option<Child> {
+        let spawned = env::current_exe().and_then(|program| {
+            let mut command = process::Command::new(program);
+            command.arg("purge").arg(root);
+            if let Some(device) = device {
+                command.arg("--device").arg(device.to_string());
+            }
+            // as gentle as the run itself
+            if throttle::is_nice() {
+                command.arg("--nice");
+            }
+            if let Some(rate) = throttle::rate() {
+                command.arg("--rate-limit").arg(rate.to_string());
+            }
+            // freed space and failures outlive the run in the purge log
+            let log = self::purge_log();
+            let output = log
+                .as_ref()
+                .and_then(|log| log.try_clone().ok())
+                .map_or(Stdio::null(), Stdio::from);
+            command
+                .stdin(Stdio::piped())
+                .stdout(output)
+                .stderr(log.map_or(Stdio::null(), Stdio::from));
+            // not interrupted along with the terminal
+            #[cfg(unix)]
+            std::os::unix::process::CommandExt::process_group(&mut command, 0);
+            command.spawn()
+        });
+
+        match spawned {
+            Ok(child) => Some(child),
             Err(e) => {
-                eprintln!("Error reading directory: {}", e);
+                log::verbose(format!(
+                    "Can't remove in background ({}), removing now...",
+                    e
+                ));
+                None
             }
         }
+    }
 
-        children
+    /// Log of background removals, appended by every purge process.
+    fn purge_log() -> Option<fs::File> {
+        let dir = history::dir().ok()?;
+        fs::create_dir_all(&dir).ok()?;
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join("purge.log"))
+            .ok()
     }
 
-    fn find<T: AsRef<str>>(item: T, list: &[String]) -> Option<usize> {
+    /// Send `detached` folders to the purge process, which removes them once
+    /// its input is closed. If it's gone, they are reclaimed by a later run.
+    fn hand_over(mut purger: Child, detached: &[PathBuf]) {
+        let Some(mut input) = purger.stdin.take() else {
+            return;
+        };
+        if let Err(e) = self::write_paths(&mut input, detached) {
+            log::error(format!(
+                "can't remove detached folders in background: {}",
+                e
+            ));
+        }
+    }
+
+    /// Write `paths` separated by NUL, which no path contains.
+    pub fn write_paths<W: Write>(writer: &mut W, paths: &[PathBuf]) -> io::Result<()> {
+        for path in paths {
+            writer.write_all(path.as_os_str().as_encoded_bytes())?;
+            writer.write_all(b"\0")?;
+        }
+        writer.flush()
+    }
+
+    /// Paths written by `write_paths`.
+    pub fn read_paths<R: Read>(mut reader: R) -> io::Result<Vec<PathBuf>> {
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes)?;
+        Ok(bytes
+            .split(|byte| *byte == 0)
+            .filter(|path| !path.is_empty())
+            .map(self::path_from_bytes)
+            .collect())
+    }
+
+    #[cfg(unix)]
+    fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+        use std::os::unix::ffi::OsStrExt;
+        PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
+    }
+
+    #[cfg(not(unix))]
+    fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+        PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+    }
+
+    /// Decision of `config` on `path`, walking down from destination with the
+    /// same steps as `scan`.
+    pub fn explain(config: &Config, path: &Path, options: &Options) -> Decision {
+        let scope = Scope::new(0, config);
+        if fs::symlink_metadata(path).is_err() {
+            return Decision::Missing;
+        }
+        let Ok(relative) = path.strip_prefix(&scope.destination) else {
+            return Decision::Outside;
+        };
+        if relative.as_os_str().is_empty() {
+            return Decision::Destination;
+        }
+
+        let mut current = scope.destination.clone();
+        for (index, component) in relative.components().enumerate() {
+            let depth = index + 1;
+            current.push(component);
+            let Ok(entry) = WalkEntry::from_path(&current, depth) else {
+                return Decision::Missing;
+            };
+            let last = current == path;
+
+            match scope.step(&entry) {
+                Step::Trash { stale } => {
+                    return Decision::Detached {
+                        path: current,
+                        reclaimed: stale,
+                    };
+                }
+                Step::Excluded(index) => {
+                    let exclude = config.exclude.as_deref().unwrap_or_default();
+                    return Decision::Excluded {
+                        path: current,
+                        exclude: exclude[index].clone(),
+                    };
+                }
+                Step::OtherFileSystem => return Decision::OtherFileSystem { path: current },
+                Step::Matched(pattern) => {
+                    if !options.force
+                        && let Some(protection) = git::protection(&current)
+                    {
+                        return Decision::Protected {
+                            path: current,
+                            pattern,
+                            reason: protection.message().to_string(),
+                        };
+                    }
+                    return match last {
+                        true => Decision::Matched { pattern },
+                        false => Decision::Inside {
+                            path: current,
+                            pattern,
+                        },
+                    };
+                }
+                Step::Search if !last => {}
+                // a parent which isn't searched
+                Step::Leaf if !last => {
+                    return match entry.is_symlink() {
+                        true => Decision::Symlink { path: current },
+                        false => Decision::TooDeep {
+                            max_depth: config.max_depth.unwrap_or_default(),
+                        },
+                    };
+                }
+                Step::Search | Step::Leaf => {
+                    let min_depth = config.min_depth.unwrap_or_default();
+                    if depth < min_depth && scope.matcher.check(&entry).is_match() {
+                        return Decision::TooShallow { min_depth };
+                    }
+                }
+            }
+        }
+        Decision::NoMatch
+    }
+
+    pub fn find<T: AsRef<str>>(item: T, list: &[String]) -> Option<usize> {
         let item = item.as_ref();
         list.iter()
             .position(|n| n.to_lowercase() == item.to_lowercase())
     }
 
-    pub fn pattern_check<P: AsRef<Path>>(
-        path: P,
-        patterns: &[String],
-        kind: &Kind,
-    ) -> Option<usize> {
+    fn is_protected<P: AsRef<Path>>(path: P, report: &mut EntryReport) -> bool {
         let path = path.as_ref();
-        // check for folder
-        if *kind == Kind::Folder && path.is_dir() {
-            let name = path
-                .file_name()
-                .unwrap_or_default()
-                .to_str()
-                .unwrap_or_default();
-            self::find(name, patterns)
-            // patterns
-            //     .iter()
-            //     .position(|n| n.to_lowercase() == name.to_lowercase())
-        } else if *kind == Kind::File && path.is_file() {
-            let extn = path
-                .extension()
-                .unwrap_or_default()
-                .to_str()
-                .unwrap_or_default();
-            self::find(extn, patterns)
-            // patterns
-            //     .iter()
-            //     .position(|n| n.to_lowercase() == extn.to_lowercase())
-        } else {
-            None
+        match self::protection(path) {
+            Some(reason) => {
+                report.skip(path, reason);
+                true
+            }
+            None => false,
         }
     }
 
-    pub fn remove_item<P: AsRef<Path>>(path: P) -> std::io::Result<()> {
-        if path.as_ref().is_file() {
-            fs::remove_file(path)
-        } else {
-            fs::remove_dir_all(path)
-        }
+    /// Why removing `path` would lose content managed by git, if it would.
+    fn protection(path: &Path) -> Option<String> {
+        git::protection(path)
+            .map(|protection| format!("{}, use --force to remove it", protection.message()))
+    }
+
+    /// Remove `path` which lives under `root`, without following any symbolic
+    /// link, and return the space freed (refer `remove::remove`).
+    pub fn remove_item<R: AsRef<Path>, P: AsRef<Path>>(
+        root: R,
+        path: P,
+        device: Option<u64>,
+    ) -> std::io::Result<Size> {
+        remove::remove(root, path, device)
+    }
+
+    #[cfg(unix)]
+    fn device(path: &Path) -> Option<u64> {
+        use std::os::unix::fs::MetadataExt;
+        stats::stat();
+        fs::metadata(path).ok().map(|metadata| metadata.dev())
+    }
+
+    // device IDs are not available, so every path is on the same file system.
+    #[cfg(not(unix))]
+    fn device(_path: &Path) -> Option<u64> {
+        None
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::report::Status;
+    use std::{collections::HashSet, sync::Arc};
 
     #[test]
     fn create_manager() {
@@ -341,7 +1623,23 @@ mod tests {
             manager,
             Manager {
                 configs: vec![],
-                dryrun: false
+                dryrun: false,
+                force: false,
+                jobs: 0,
+                no_cache: false,
+                nice: false,
+                rate_limit: None,
+                background: false,
+                wait: false,
+                no_lock: false,
+                plan: None,
+                save_plan: None,
+                export: None,
+                format: Format::Human,
+                level: Level::Normal,
+                config: None,
+                resume: None,
+                command: None,
             }
         );
     }
@@ -367,8 +1665,28 @@ mod tests {
                         String::from("release"),
                     ],
                     exclude: None,
+                    one_file_system: false,
+                    min_depth: None,
+                    max_depth: None,
+                    rules: vec![],
                 }],
-                dryrun: false
+                dryrun: false,
+                force: false,
+                jobs: 0,
+                no_cache: false,
+                nice: false,
+                rate_limit: None,
+                background: false,
+                wait: false,
+                no_lock: false,
+                plan: None,
+                save_plan: None,
+                export: None,
+                format: Format::Human,
+                level: Level::Normal,
+                config: None,
+                resume: None,
+                command: None,
             }
         );
     }
@@ -401,8 +1719,28 @@ mod tests {
                         String::from("release"),
                     ],
                     exclude: None,
+                    one_file_system: false,
+                    min_depth: None,
+                    max_depth: None,
+                    rules: vec![],
                 }],
-                dryrun: false
+                dryrun: false,
+                force: false,
+                jobs: 0,
+                no_cache: false,
+                nice: false,
+                rate_limit: None,
+                background: false,
+                wait: false,
+                no_lock: false,
+                plan: None,
+                save_plan: None,
+                export: None,
+                format: Format::Human,
+                level: Level::Normal,
+                config: None,
+                resume: None,
+                command: None,
             }
         );
     }
@@ -422,7 +1760,529 @@ mod tests {
             ],
             exclude: vec![],
             dryrun: true,
+            force: false,
         };
         helper::remove_as_mut(&mut item);
     }
+
+    #[test]
+    fn keep_git_tracked_folder() {
+        use std::process::Command;
+
+        let dir = tempfile::tempdir().unwrap();
+        let build = dir.path().join("build");
+        fs::create_dir(&build).unwrap();
+        fs::write(build.join("keep.txt"), "on purpose").unwrap();
+        for args in [
+            vec!["init"],
+            vec!["add", "build"],
+            vec!["commit", "-m", "build"],
+        ] {
+            Command::new("git")
+                .arg("-C")
+                .arg(dir.path())
+                .args(["-c", "user.name=neaten", "-c", "user.email=neaten@test"])
+                .args(args)
+                .output()
+                .unwrap();
+        }
+
+        let config = Config::new(dir.path(), Kind::Folder, vec!["build"], None);
+        let mut report = EntryReport::default();
+        let entry = helper::scan(
+            &config,
+            &helper::Options {
+                force: false,
+                ..Default::default()
+            },
+            &mut report,
+        );
+        assert!(entry.items.is_empty());
+        assert_eq!(report.items[0].status, Status::Skipped);
+
+        let mut report = EntryReport::default();
+        let entry = helper::scan(
+            &config,
+            &helper::Options {
+                force: true,
+                ..Default::default()
+            },
+            &mut report,
+        );
+        helper::apply(&entry, false, false, &mut report);
+        assert!(!build.exists());
+        assert_eq!(report.items[0].status, Status::Removed);
+    }
+
+    #[test]
+    fn remove_on_one_file_system() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("proj").join("target");
+        fs::create_dir_all(target.join("debug")).unwrap();
+        fs::write(target.join("debug").join("app"), "binary").unwrap();
+
+        let mut config = Config::new(dir.path(), Kind::Folder, vec!["target"], None);
+        config.one_file_system = true;
+        let mut report = EntryReport::default();
+        let entry = helper::scan(
+            &config,
+            &helper::Options {
+                force: true,
+                ..Default::default()
+            },
+            &mut report,
+        );
+        helper::apply(&entry, false, false, &mut report);
+        assert!(!target.exists());
+        assert!(dir.path().join("proj").exists());
+    }
+
+    #[test]
+    fn scan_with_depth() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("target")).unwrap();
+        fs::create_dir_all(dir.path().join("proj").join("target")).unwrap();
+        fs::create_dir_all(dir.path().join("a").join("b").join("proj").join("target")).unwrap();
+
+        let mut config = Config::new(dir.path(), Kind::Folder, vec!["target"], None);
+        config.min_depth = Some(2);
+        config.max_depth = Some(2);
+        let mut report = EntryReport::default();
+        let entry = helper::scan(
+            &config,
+            &helper::Options {
+                force: true,
+                ..Default::default()
+            },
+            &mut report,
+        );
+        let paths: Vec<PathBuf> = entry.items.into_iter().map(|item| item.path).collect();
+        assert_eq!(paths, vec![dir.path().join("proj").join("target")]);
+
+        let options = helper::Options {
+            force: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            helper::explain(&config, &dir.path().join("target"), &options),
+            Decision::TooShallow { min_depth: 2 }
+        );
+        let deep = dir.path().join("a").join("b").join("proj").join("target");
+        assert_eq!(
+            helper::explain(&config, &deep, &options),
+            Decision::TooDeep { max_depth: 2 }
+        );
+    }
+
+    #[test]
+    fn measure_grown_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = tempfile::tempdir().unwrap();
+        let target = dir.path().join("target");
+        fs::create_dir_all(target.join("debug")).unwrap();
+        let old = SystemTime::now() - std::time::Duration::from_secs(60);
+        fs::File::open(&target).unwrap().set_modified(old).unwrap();
+
+        let cache = Cache::load(state.path(), &[]);
+        let before = self::measure(&target, Some(old), "target", &cache);
+        cache.save().unwrap();
+
+        // the folder itself is unchanged
+        fs::write(target.join("debug").join("app"), vec![0; 64 * 1024]).unwrap();
+        let cache = Cache::load(state.path(), &[]);
+        let after = self::measure(&target, Some(old), "target", &cache);
+        assert!(after.size.apparent >= before.size.apparent + 64 * 1024);
+    }
+
+    #[test]
+    fn scan_with_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = tempfile::tempdir().unwrap();
+        let src = dir.path().join("proj").join("src");
+        fs::create_dir_all(src.join("app")).unwrap();
+        fs::create_dir_all(dir.path().join("proj").join("target")).unwrap();
+        // old enough to be cached
+        let old = SystemTime::now() - std::time::Duration::from_secs(60);
+        let age = |path: &Path| fs::File::open(path).unwrap().set_modified(old).unwrap();
+        [src.as_path(), &src.join("app")]
+            .iter()
+            .for_each(|path| age(path));
+
+        let configs = vec![Config::new(dir.path(), Kind::Folder, vec!["target"], None)];
+        let scan = || {
+            let options = helper::Options {
+                force: true,
+                cache: Cache::load(state.path(), &configs),
+            };
+            let mut reports = vec![EntryReport::default()];
+            let entries = helper::scan_all(&configs, &options, &mut reports);
+            options.cache.save().unwrap();
+            let paths: Vec<PathBuf> = entries[0]
+                .items
+                .iter()
+                .map(|item| item.path.clone())
+                .collect();
+            paths
+        };
+        assert_eq!(scan(), vec![dir.path().join("proj").join("target")]);
+
+        // unchanged folders are not read again
+        fs::create_dir(src.join("app").join("target")).unwrap();
+        age(&src.join("app"));
+        assert_eq!(scan(), vec![dir.path().join("proj").join("target")]);
+
+        // modified ones are
+        fs::File::open(src.join("app"))
+            .unwrap()
+            .set_modified(SystemTime::now())
+            .unwrap();
+        assert_eq!(
+            scan(),
+            vec![
+                dir.path()
+                    .join("proj")
+                    .join("src")
+                    .join("app")
+                    .join("target"),
+                dir.path().join("proj").join("target"),
+            ]
+        );
+    }
+
+    #[test]
+    fn keep_resume_file_on_dryrun() {
+        let state = tempfile::tempdir().unwrap();
+        let path = state.path().join("resume.json");
+        Plan::new().save(&path).unwrap();
+
+        let mut manager = Manager::new();
+        manager.command = Some(Command::Resume);
+        manager.resume = Some(path.clone());
+        manager.dryrun = true;
+        manager.resumed().unwrap();
+        assert!(path.exists());
+
+        manager.dryrun = false;
+        manager.resumed().unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn resume_file_per_destinations() {
+        let dir = tempfile::tempdir().unwrap();
+        let (a, b) = (dir.path().join("a"), dir.path().join("b"));
+        fs::create_dir(&a).unwrap();
+        fs::create_dir(&b).unwrap();
+        let state = dir.path().join("state");
+
+        let file = resume_file(state.clone(), &[&a, &b]);
+        assert_eq!(file, resume_file(state.clone(), &[&b, &a.join(".")]));
+        assert_ne!(file, resume_file(state.clone(), &[&a]));
+        assert_ne!(file, resume_file(state, &[&b]));
+    }
+
+    #[test]
+    fn resume_configured_destinations() {
+        let dir = tempfile::tempdir().unwrap();
+        let (a, b) = (dir.path().join("a"), dir.path().join("b"));
+        fs::create_dir(&a).unwrap();
+        fs::create_dir(&b).unwrap();
+        let state = dir.path().join("state");
+        fs::create_dir(&state).unwrap();
+
+        let mut manager = Manager::new();
+        manager.add(Config::new(&a, Kind::Folder, vec!["target"], None));
+        let err = manager.load_resume(state.clone()).unwrap_err();
+        assert!(err.message().contains("no interrupted run"));
+
+        // plan of another destination under the name of this one
+        let mut plan = Plan::new();
+        plan.add(Entry {
+            destination: b.clone(),
+            ..Entry::default()
+        });
+        plan.save(resume_file(state.clone(), &[&a])).unwrap();
+        let err = manager.load_resume(state.clone()).unwrap_err();
+        assert!(err.message().contains("not a destination of the config"));
+
+        let mut plan = Plan::new();
+        plan.add(Entry {
+            destination: a.clone(),
+            ..Entry::default()
+        });
+        plan.save(resume_file(state.clone(), &[&a])).unwrap();
+        manager.load_resume(state.clone()).unwrap();
+        assert_eq!(manager.plan, Some(plan));
+        assert_eq!(manager.resume, Some(resume_file(state, &[&a])));
+    }
+
+    #[test]
+    fn purge_detached_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("proj").join("target");
+        let src = dir.path().join("proj").join("src");
+        fs::create_dir_all(target.join("debug")).unwrap();
+        fs::create_dir_all(&src).unwrap();
+
+        let Ok(remove::Detached::Renamed(trash)) =
+            remove::detach(dir.path(), &target, None, process::id())
+        else {
+            panic!("{:?} wasn't renamed", target);
+        };
+        let mut input = vec![];
+        helper::write_paths(&mut input, &[trash.clone(), src.clone()]).unwrap();
+        let paths = helper::read_paths(input.as_slice()).unwrap();
+        assert_eq!(paths, vec![trash.clone(), src.clone()]);
+
+        let manager = Manager::new();
+        manager.purge(dir.path(), None, &paths).unwrap();
+        assert!(!trash.exists());
+        assert!(src.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn reclaim_stale_trash() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut child = process::Command::new("true").spawn().unwrap();
+        let gone = child.id();
+        child.wait().unwrap();
+        let stale = dir.path().join(format!(".neaten-trash-{}-0", gone));
+        let live = dir
+            .path()
+            .join(format!(".neaten-trash-{}-0", process::id()));
+        fs::create_dir_all(stale.join("target")).unwrap();
+        fs::create_dir_all(live.join("target")).unwrap();
+
+        let config = Config::new(dir.path(), Kind::Folder, vec!["target"], None);
+        let mut report = EntryReport::default();
+        let entry = helper::scan(&config, &helper::Options::default(), &mut report);
+
+        // whatever its content, and never searched
+        let planned: Vec<PathBuf> = entry.items.iter().map(|item| item.path.clone()).collect();
+        assert_eq!(planned, vec![stale]);
+    }
+
+    #[test]
+    fn scan_and_remove_in_parallel() {
+        let dir = tempfile::tempdir().unwrap();
+        for index in 0..50 {
+            let proj = dir.path().join(format!("proj{:02}", index));
+            fs::create_dir_all(proj.join("target").join("debug")).unwrap();
+            fs::create_dir_all(proj.join("vendor").join("target")).unwrap();
+        }
+
+        let config = Config::new(
+            dir.path(),
+            Kind::Folder,
+            vec!["target"],
+            Some(vec!["vendor"]),
+        );
+        let pool = ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+        let mut report = EntryReport::default();
+        let entry = pool.install(|| {
+            let entry = helper::scan(
+                &config,
+                &helper::Options {
+                    force: true,
+                    ..Default::default()
+                },
+                &mut report,
+            );
+            helper::apply(&entry, false, false, &mut report);
+            entry
+        });
+
+        // deterministic order, excluded folders are kept
+        let expected: Vec<PathBuf> = (0..50)
+            .map(|index| dir.path().join(format!("proj{:02}", index)).join("target"))
+            .collect();
+        let planned: Vec<PathBuf> = entry.items.iter().map(|item| item.path.clone()).collect();
+        let reported: Vec<PathBuf> = report.items.iter().map(|item| item.path.clone()).collect();
+        assert_eq!(planned, expected);
+        assert_eq!(reported, expected);
+        assert_eq!(report.excluded.len(), 50);
+        assert!(expected.iter().all(|path| !path.exists()));
+        assert!(
+            dir.path()
+                .join("proj07")
+                .join("vendor")
+                .join("target")
+                .exists()
+        );
+    }
+
+    #[test]
+    fn scan_wide_folder_in_parallel() {
+        /// Matches nothing, slowly, and records threads which checked entries.
+        #[derive(Debug, Default)]
+        struct Threads(Arc<Mutex<HashSet<Option<usize>>>>);
+
+        impl Matcher for Threads {
+            fn check(&self, _entry: &WalkEntry) -> Verdict {
+                thread::sleep(std::time::Duration::from_millis(1));
+                self.0.lock().unwrap().insert(rayon::current_thread_index());
+                Verdict::NoMatch(String::from("never"))
+            }
+        }
+
+        // a single folder at the top, all the work is below it
+        let dir = tempfile::tempdir().unwrap();
+        let modules = dir.path().join("app").join("node_modules");
+        for index in 0..64 {
+            let lib = modules.join(format!("pkg{:02}", index)).join("lib");
+            fs::create_dir_all(&lib).unwrap();
+            fs::write(lib.join("index.js"), "").unwrap();
+        }
+
+        let threads = Threads::default();
+        let checked = threads.0.clone();
+        let config = Config::new(dir.path(), Kind::Folder, vec!["target"], None).rule(threads);
+        let pool = ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+        let mut report = EntryReport::default();
+        let entry = pool.install(|| helper::scan(&config, &Default::default(), &mut report));
+
+        assert!(entry.items.is_empty());
+        assert!(report.errors.is_empty());
+        assert!(checked.lock().unwrap().len() > 1);
+    }
+
+    #[test]
+    fn scan_deep_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut path = dir.path().to_path_buf();
+        for _ in 0..500 {
+            path.push("d");
+        }
+        let target = path.join("target");
+        fs::create_dir_all(&target).unwrap();
+
+        // tasks of sub-folders don't pile up on the stack of their parent
+        let config = Config::new(dir.path(), Kind::Folder, vec!["target"], None);
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(2)
+            .stack_size(256 * 1024)
+            .build()
+            .unwrap();
+        let mut report = EntryReport::default();
+        let entry = pool.install(|| helper::scan(&config, &Default::default(), &mut report));
+        assert_eq!(entry.items.len(), 1);
+        assert_eq!(entry.items[0].path, target);
+    }
+
+    #[test]
+    fn scan_shared_destination() {
+        let dir = tempfile::tempdir().unwrap();
+        let proj = dir.path().join("proj");
+        fs::create_dir_all(proj.join("target")).unwrap();
+        fs::create_dir_all(proj.join("vendor").join("target")).unwrap();
+        fs::write(proj.join("build.log"), "log").unwrap();
+        fs::write(proj.join("vendor").join("build.log"), "log").unwrap();
+        fs::write(proj.join("target").join("build.log"), "log").unwrap();
+
+        let configs = vec![
+            Config::new(
+                dir.path(),
+                Kind::Folder,
+                vec!["target"],
+                Some(vec!["vendor"]),
+            ),
+            Config::new(dir.path(), Kind::File, vec!["log"], None),
+            Config::new(proj.join("vendor"), Kind::Folder, vec!["target"], None),
+        ];
+        let mut reports: Vec<EntryReport> =
+            configs.iter().map(|_| EntryReport::default()).collect();
+        let entries = helper::scan_all(
+            &configs,
+            &helper::Options {
+                force: true,
+                ..Default::default()
+            },
+            &mut reports,
+        );
+
+        // every match is attributed to its own entry
+        let paths = |entry: &Entry| -> Vec<PathBuf> {
+            entry.items.iter().map(|item| item.path.clone()).collect()
+        };
+        assert_eq!(paths(&entries[0]), vec![proj.join("target")]);
+        assert_eq!(
+            paths(&entries[1]),
+            vec![
+                proj.join("build.log"),
+                proj.join("target").join("build.log"),
+                proj.join("vendor").join("build.log"),
+            ]
+        );
+        assert_eq!(paths(&entries[2]), vec![proj.join("vendor").join("target")]);
+        assert_eq!(reports[0].excluded, vec![proj.join("vendor")]);
+        assert!(reports[1].excluded.is_empty());
+        assert_eq!(reports[2].destination, proj.join("vendor"));
+
+        // same as scanning each entry on its own
+        for (config, entry) in configs.iter().zip(&entries) {
+            let alone = helper::scan(
+                config,
+                &helper::Options {
+                    force: true,
+                    ..Default::default()
+                },
+                &mut EntryReport::default(),
+            );
+            assert_eq!(paths(&alone), paths(entry));
+        }
+    }
+
+    #[test]
+    fn explain_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("proj").join("target");
+        fs::create_dir_all(target.join("debug")).unwrap();
+        fs::create_dir_all(dir.path().join("vendor").join("target")).unwrap();
+        let trash = dir
+            .path()
+            .join(format!(".neaten-trash-{}-0", process::id()));
+        fs::create_dir_all(trash.join("target")).unwrap();
+
+        let config = Config::new(
+            dir.path(),
+            Kind::Folder,
+            vec!["target"],
+            Some(vec!["vendor"]),
+        );
+        let options = helper::Options {
+            force: true,
+            ..Default::default()
+        };
+        let explain = |path: &Path| helper::explain(&config, path, &options);
+
+        let pattern = String::from("target");
+        assert_eq!(explain(&target), Decision::Matched { pattern });
+        assert_eq!(
+            explain(&target.join("debug")),
+            Decision::Inside {
+                path: target.clone(),
+                pattern: String::from("target")
+            }
+        );
+        assert_eq!(
+            explain(&dir.path().join("vendor").join("target")),
+            Decision::Excluded {
+                path: dir.path().join("vendor"),
+                exclude: String::from("vendor")
+            }
+        );
+        // scans never search detached folders
+        assert_eq!(
+            explain(&trash.join("target")),
+            Decision::Detached {
+                path: trash.clone(),
+                reclaimed: false
+            }
+        );
+        assert_eq!(explain(&dir.path().join("proj")), Decision::NoMatch);
+        assert_eq!(explain(dir.path()), Decision::Destination);
+        assert_eq!(explain(&dir.path().join("missing")), Decision::Missing);
+        assert_eq!(explain(Path::new("/")), Decision::Outside);
+    }
 }
@@ -0,0 +1,539 @@
+//! Rules telling which entries of a destination are items to be removed.
+//!
+//! `patterns` of a config entry match names of folders or extensions of
+//! files, library users can add their own rules to a config entry with
+//! [`Config::rule`](crate::Config::rule), built from the matchers of this
+//! module or from their own implementation of [`Matcher`].
+//!
+//! ```no_run
+//! use neaten::{Config, Kind, Scanner, matcher::{self, MinAge, MinSize, Name}};
+//! use std::time::Duration;
+//!
+//! // big `target` folders untouched for a month
+//! let rule = matcher::all(vec![
+//!     Box::new(Name::new(["target"]).kind(Kind::Folder)),
+//!     Box::new(MinSize::new(1024 * 1024 * 1024)),
+//!     Box::new(MinAge::new(Duration::from_secs(30 * 24 * 60 * 60))),
+//! ]);
+//! let configs = vec![Config::new("/usr/sample/rust", Kind::Folder, Vec::<String>::new(), None).rule(rule)];
+//! let (plan, _) = Scanner::new(&configs).scan();
+//! ```
+
+use crate::{
+    Kind,
+    error::{AppError, AppErrorKind},
+    size::{self, Bytes, Size},
+    walker::WalkEntry,
+};
+use globset::{GlobBuilder, GlobMatcher};
+use std::{
+    collections::HashSet,
+    fmt::{Debug, Formatter, Result as FmtResult},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+/// Decides whether an entry is an item to be removed.
+///
+/// Matched entries are removed along with their content, the content of
+/// other directories is searched.
+pub trait Matcher: Debug + Send + Sync {
+    fn check(&self, entry: &WalkEntry) -> Verdict;
+}
+
+/// Outcome of a matcher for an entry, with its reason.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Verdict {
+    /// entry is an item to be removed, e.g. because of the pattern it matches.
+    Match(String),
+    /// entry isn't an item, e.g. because of the condition it doesn't meet.
+    NoMatch(String),
+}
+
+impl Verdict {
+    pub fn is_match(&self) -> bool {
+        matches!(self, Verdict::Match(_))
+    }
+
+    pub fn reason(&self) -> &str {
+        match self {
+            Verdict::Match(reason) | Verdict::NoMatch(reason) => reason,
+        }
+    }
+}
+
+/// Rule of a config entry, matching items besides its `patterns`.
+#[derive(Clone)]
+pub struct Rule(Arc<dyn Matcher>);
+
+impl Rule {
+    pub fn new<M: Matcher + 'static>(matcher: M) -> Rule {
+        Rule(Arc::new(matcher))
+    }
+}
+
+impl Matcher for Rule {
+    fn check(&self, entry: &WalkEntry) -> Verdict {
+        self.0.check(entry)
+    }
+}
+
+impl Debug for Rule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        self.0.fmt(f)
+    }
+}
+
+/// Rules are equal when they are the same rule.
+impl PartialEq for Rule {
+    fn eq(&self, other: &Rule) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Matcher for Box<dyn Matcher> {
+    fn check(&self, entry: &WalkEntry) -> Verdict {
+        self.as_ref().check(entry)
+    }
+}
+
+/// Entries named after one of the names, whatever their case.
+#[derive(Debug)]
+pub struct Name {
+    names: Vec<String>,
+    /// lower case names, to compare with.
+    lower: Vec<String>,
+    kind: Option<Kind>,
+}
+
+impl Name {
+    pub fn new<I, S>(names: I) -> Name
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let names: Vec<String> = names.into_iter().map(Into::into).collect();
+        let lower = names.iter().map(|name| name.to_lowercase()).collect();
+        Name {
+            names,
+            lower,
+            kind: None,
+        }
+    }
+
+    /// Only match folders, or files.
+    pub fn kind(mut self, kind: Kind) -> Name {
+        self.kind = Some(kind);
+        self
+    }
+}
+
+impl Matcher for Name {
+    fn check(&self, entry: &WalkEntry) -> Verdict {
+        match self.kind {
+            Some(Kind::Folder) if !entry.is_dir() => {
+                return Verdict::NoMatch(String::from("not a folder"));
+            }
+            Some(Kind::File) if !entry.is_file() => {
+                return Verdict::NoMatch(String::from("not a file"));
+            }
+            _ => {}
+        }
+
+        let name = entry
+            .file_name()
+            .to_str()
+            .unwrap_or_default()
+            .to_lowercase();
+        match self.lower.iter().position(|lower| *lower == name) {
+            Some(index) => Verdict::Match(self.names[index].clone()),
+            None => Verdict::NoMatch(format!("name isn't any of {:?}", self.names)),
+        }
+    }
+}
+
+/// Files with one of the extensions, whatever their case.
+#[derive(Debug)]
+pub struct Extension {
+    extensions: Vec<String>,
+    /// lower case extensions, to compare with.
+    lower: Vec<String>,
+}
+
+impl Extension {
+    pub fn new<I, S>(extensions: I) -> Extension
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let extensions: Vec<String> = extensions.into_iter().map(Into::into).collect();
+        let lower = extensions.iter().map(|extn| extn.to_lowercase()).collect();
+        Extension { extensions, lower }
+    }
+}
+
+impl Matcher for Extension {
+    fn check(&self, entry: &WalkEntry) -> Verdict {
+        if !entry.is_file() {
+            return Verdict::NoMatch(String::from("not a file"));
+        }
+
+        let extn = entry
+            .path()
+            .extension()
+            .unwrap_or_default()
+            .to_str()
+            .unwrap_or_default()
+            .to_lowercase();
+        match self.lower.iter().position(|lower| *lower == extn) {
+            Some(index) => Verdict::Match(self.extensions[index].clone()),
+            None => Verdict::NoMatch(format!("extension isn't any of {:?}", self.extensions)),
+        }
+    }
+}
+
+/// Entries matching a glob, whatever their case, e.g. `*.log` or `cmake-build-*`.
+///
+/// A glob with a `/` matches the whole path, e.g. `**/web/dist`, otherwise
+/// it matches the name. `*` never matches a `/`.
+#[derive(Debug)]
+pub struct Glob {
+    glob: String,
+    matcher: GlobMatcher,
+}
+
+impl Glob {
+    pub fn new<S: Into<String>>(glob: S) -> crate::Result<Glob> {
+        let glob = glob.into();
+        let matcher = GlobBuilder::new(&glob)
+            .case_insensitive(true)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| AppError::new(AppErrorKind::Usage, e.to_string()))?
+            .compile_matcher();
+        Ok(Glob { glob, matcher })
+    }
+}
+
+impl Matcher for Glob {
+    fn check(&self, entry: &WalkEntry) -> Verdict {
+        let matched = match self.glob.contains('/') {
+            true => self.matcher.is_match(entry.path()),
+            false => self.matcher.is_match(entry.file_name()),
+        };
+        match matched {
+            true => Verdict::Match(self.glob.clone()),
+            false => Verdict::NoMatch(format!("doesn't match {:?}", self.glob)),
+        }
+    }
+}
+
+/// Entries whose name matches a regular expression, e.g. `^cmake-build-`.
+#[derive(Debug)]
+pub struct Regex {
+    regex: regex::Regex,
+}
+
+impl Regex {
+    pub fn new(regex: &str) -> crate::Result<Regex> {
+        let regex = regex::Regex::new(regex)
+            .map_err(|e| AppError::new(AppErrorKind::Usage, e.to_string()))?;
+        Ok(Regex { regex })
+    }
+}
+
+impl Matcher for Regex {
+    fn check(&self, entry: &WalkEntry) -> Verdict {
+        match self.regex.is_match(&entry.file_name().to_string_lossy()) {
+            true => Verdict::Match(self.regex.to_string()),
+            false => Verdict::NoMatch(format!("name doesn't match {:?}", self.regex.as_str())),
+        }
+    }
+}
+
+/// Entries using at least this many bytes on disk, along with their content.
+///
+/// Measuring a folder also tells which of its sub-folders are big enough, so
+/// they aren't measured again when the scan searches them.
+#[derive(Debug)]
+pub struct MinSize {
+    bytes: u64,
+    measured: Mutex<Measured>,
+}
+
+/// Folders measured by a `MinSize`.
+#[derive(Debug, Default)]
+struct Measured {
+    /// folders whose whole content was measured.
+    roots: HashSet<PathBuf>,
+    /// folders of those which are big enough.
+    big: HashSet<PathBuf>,
+}
+
+impl MinSize {
+    pub fn new(bytes: u64) -> MinSize {
+        MinSize {
+            bytes,
+            measured: Mutex::new(Measured::default()),
+        }
+    }
+
+    /// Whether folder `path` is big enough, measured along with its
+    /// sub-folders unless one of its parents already was.
+    fn big_folder(&self, path: &Path) -> bool {
+        if let Ok(measured) = self.measured.lock()
+            && let Some(big) = measured.known(path)
+        {
+            return big;
+        }
+
+        let mut big = HashSet::new();
+        let size = size::measure(path, |folder, size| {
+            if size.disk >= self.bytes {
+                big.insert(folder);
+            }
+        });
+        if let Ok(mut measured) = self.measured.lock() {
+            measured.roots.insert(path.to_path_buf());
+            measured.big.extend(big);
+        }
+        size.disk >= self.bytes
+    }
+}
+
+impl Measured {
+    /// Whether folder `path` is big enough, if it was measured.
+    fn known(&self, path: &Path) -> Option<bool> {
+        if self.big.contains(path) {
+            return Some(true);
+        }
+        path.ancestors()
+            .any(|parent| self.roots.contains(parent))
+            .then_some(false)
+    }
+}
+
+impl Matcher for MinSize {
+    fn check(&self, entry: &WalkEntry) -> Verdict {
+        let big = match entry.file_type().is_dir() {
+            true => self.big_folder(entry.path()),
+            false => Size::of(entry.path()).disk >= self.bytes,
+        };
+        match big {
+            true => Verdict::Match(format!("at least {}", Bytes(self.bytes))),
+            false => Verdict::NoMatch(format!("smaller than {}", Bytes(self.bytes))),
+        }
+    }
+}
+
+/// Entries not modified for at least this long, the content of a folder
+/// isn't taken into account.
+#[derive(Debug)]
+pub struct MinAge {
+    age: Duration,
+}
+
+impl MinAge {
+    pub fn new(age: Duration) -> MinAge {
+        MinAge { age }
+    }
+}
+
+impl Matcher for MinAge {
+    fn check(&self, entry: &WalkEntry) -> Verdict {
+        let age = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+        match age {
+            Some(age) if age >= self.age => {
+                Verdict::Match(format!("unmodified for {}", self::duration(self.age)))
+            }
+            _ => Verdict::NoMatch(format!("modified within {}", self::duration(self.age))),
+        }
+    }
+}
+
+/// Entries matched by every matcher.
+#[derive(Debug)]
+pub struct All(Vec<Box<dyn Matcher>>);
+
+/// Entries matched by at least one matcher.
+#[derive(Debug)]
+pub struct Any(Vec<Box<dyn Matcher>>);
+
+/// Entries not matched by the matcher.
+#[derive(Debug)]
+pub struct Not(Box<dyn Matcher>);
+
+pub fn all(matchers: Vec<Box<dyn Matcher>>) -> All {
+    All(matchers)
+}
+
+pub fn any(matchers: Vec<Box<dyn Matcher>>) -> Any {
+    Any(matchers)
+}
+
+pub fn not<M: Matcher + 'static>(matcher: M) -> Not {
+    Not(Box::new(matcher))
+}
+
+impl Matcher for All {
+    fn check(&self, entry: &WalkEntry) -> Verdict {
+        let mut reasons = vec![];
+        for matcher in &self.0 {
+            match matcher.check(entry) {
+                Verdict::Match(reason) => reasons.push(reason),
+                no_match => return no_match,
+            }
+        }
+        Verdict::Match(reasons.join(" and "))
+    }
+}
+
+impl Matcher for Any {
+    fn check(&self, entry: &WalkEntry) -> Verdict {
+        let mut reasons = vec![];
+        for matcher in &self.0 {
+            match matcher.check(entry) {
+                Verdict::NoMatch(reason) => reasons.push(reason),
+                matched => return matched,
+            }
+        }
+        Verdict::NoMatch(reasons.join(" and "))
+    }
+}
+
+impl Matcher for Not {
+    fn check(&self, entry: &WalkEntry) -> Verdict {
+        match self.0.check(entry) {
+            Verdict::Match(reason) => Verdict::NoMatch(reason),
+            Verdict::NoMatch(reason) => Verdict::Match(reason),
+        }
+    }
+}
+
+/// Matcher of `patterns` for items of `kind`, as written in config files.
+pub(crate) fn patterns(kind: &Kind, patterns: &[String]) -> Box<dyn Matcher> {
+    match kind {
+        Kind::Folder => Box::new(Name::new(patterns.iter().cloned()).kind(Kind::Folder)),
+        Kind::File => Box::new(Extension::new(patterns.iter().cloned())),
+    }
+}
+
+/// Duration in its largest whole unit, e.g. `30 days`.
+fn duration(duration: Duration) -> String {
+    const UNITS: [(&str, u64); 4] = [
+        ("day", 86400),
+        ("hour", 3600),
+        ("minute", 60),
+        ("second", 1),
+    ];
+
+    let secs = duration.as_secs();
+    let (unit, length) = UNITS
+        .iter()
+        .find(|(_, length)| secs >= *length && secs.is_multiple_of(*length))
+        .copied()
+        .unwrap_or(("second", 1));
+    let count = secs / length;
+    format!("{} {}{}", count, unit, if count == 1 { "" } else { "s" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{self, File};
+
+    #[test]
+    fn builtin_matchers() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("Target")).unwrap();
+        fs::write(dir.path().join("app.LOG"), "log").unwrap();
+        let folder = WalkEntry::from_path(dir.path().join("Target"), 1).unwrap();
+        let file = WalkEntry::from_path(dir.path().join("app.LOG"), 1).unwrap();
+
+        let name = Name::new(["target"]);
+        assert_eq!(name.check(&folder), Verdict::Match(String::from("target")));
+        assert!(!name.kind(Kind::File).check(&folder).is_match());
+
+        let extension = Extension::new(["log"]);
+        assert_eq!(extension.check(&file), Verdict::Match(String::from("log")));
+        assert!(!extension.check(&folder).is_match());
+
+        assert!(Glob::new("*.log").unwrap().check(&file).is_match());
+        assert!(Glob::new("**/target").unwrap().check(&folder).is_match());
+        assert!(!Glob::new("tar*/x").unwrap().check(&folder).is_match());
+        assert!(Glob::new("[").is_err());
+
+        assert!(Regex::new("^T.*t$").unwrap().check(&folder).is_match());
+        assert!(!Regex::new("^t").unwrap().check(&folder).is_match());
+        assert!(Regex::new("(").is_err());
+
+        assert!(MinSize::new(0).check(&file).is_match());
+        assert!(!MinSize::new(1024 * 1024).check(&file).is_match());
+
+        let month = Duration::from_secs(30 * 24 * 60 * 60);
+        assert!(!MinAge::new(month).check(&file).is_match());
+        File::open(dir.path().join("app.LOG"))
+            .unwrap()
+            .set_modified(SystemTime::now() - month * 2)
+            .unwrap();
+        assert_eq!(
+            MinAge::new(month).check(&file),
+            Verdict::Match(String::from("unmodified for 30 days"))
+        );
+    }
+
+    #[test]
+    fn min_size_of_folders() {
+        let dir = tempfile::tempdir().unwrap();
+        let deps = dir.path().join("target").join("debug").join("deps");
+        fs::create_dir_all(&deps).unwrap();
+        fs::create_dir_all(dir.path().join("target").join("doc")).unwrap();
+        fs::write(deps.join("lib.rlib"), vec![1; 200 * 1024]).unwrap();
+
+        // sub-folders are known from their parent, as a scan searches them
+        let min_size = MinSize::new(100 * 1024);
+        let check = |path: &Path| {
+            let entry = WalkEntry::from_path(path, 1).unwrap();
+            min_size.check(&entry).is_match()
+        };
+        assert!(check(&dir.path().join("target")));
+        assert!(check(&dir.path().join("target").join("debug")));
+        assert!(check(&deps));
+        assert!(!check(&dir.path().join("target").join("doc")));
+        assert!(check(&deps.join("lib.rlib")));
+        let measured = min_size.measured.lock().unwrap();
+        assert_eq!(measured.roots.len(), 1);
+        assert_eq!(measured.big.len(), 3);
+    }
+
+    #[test]
+    fn combinators() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("dist")).unwrap();
+        let folder = WalkEntry::from_path(dir.path().join("dist"), 1).unwrap();
+
+        let matcher = all(vec![
+            Box::new(Name::new(["dist"])),
+            Box::new(MinSize::new(0)),
+        ]);
+        assert_eq!(
+            matcher.check(&folder),
+            Verdict::Match(String::from("dist and at least 0 B"))
+        );
+
+        let matcher = any(vec![
+            Box::new(Name::new(["build"])),
+            Box::new(Glob::new("d*").unwrap()),
+        ]);
+        assert_eq!(matcher.check(&folder), Verdict::Match(String::from("d*")));
+
+        let matcher = not(Name::new(["dist"]));
+        assert!(!matcher.check(&folder).is_match());
+        assert!(not(matcher).check(&folder).is_match());
+    }
+}
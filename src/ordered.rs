@@ -0,0 +1,143 @@
+//! Channel delivering messages of parallel producers in a fixed order.
+//!
+//! A stream is a sequence of messages and of nested streams, each fed by its
+//! own producer, e.g. one per folder searched in parallel. The receiver yields
+//! messages depth-first, in the order they were sent and streams were opened,
+//! as soon as every earlier message was yielded. So what was found early in a
+//! traversal is reported right away, whatever the scheduling of threads.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+};
+
+enum Message<T> {
+    Item(T),
+    /// nested stream, delivered before the messages sent after it.
+    Open(usize),
+    /// stream is over, its sender was dropped.
+    Close,
+}
+
+/// Producer of a stream, the stream is closed when it's dropped.
+pub struct Sender<T> {
+    id: usize,
+    /// id of the next stream.
+    ids: Arc<AtomicUsize>,
+    tx: mpsc::Sender<(usize, Message<T>)>,
+}
+
+/// Messages of every stream, in order.
+pub struct Receiver<T> {
+    rx: mpsc::Receiver<(usize, Message<T>)>,
+    /// messages received ahead of their turn, per stream.
+    queues: HashMap<usize, VecDeque<Message<T>>>,
+    /// streams being delivered, innermost last.
+    stack: Vec<usize>,
+}
+
+/// Root stream and its receiver.
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let (tx, rx) = mpsc::channel();
+    let sender = Sender {
+        id: 0,
+        ids: Arc::new(AtomicUsize::new(1)),
+        tx,
+    };
+    let receiver = Receiver {
+        rx,
+        queues: HashMap::new(),
+        stack: vec![0],
+    };
+    (sender, receiver)
+}
+
+impl<T> Sender<T> {
+    pub fn send(&self, item: T) {
+        // nobody listens anymore, e.g. the receiver panicked
+        let _ = self.tx.send((self.id, Message::Item(item)));
+    }
+
+    /// Open a nested stream at this point of the stream.
+    pub fn open(&self) -> Sender<T> {
+        let id = self.ids.fetch_add(1, Ordering::Relaxed);
+        let _ = self.tx.send((self.id, Message::Open(id)));
+        Sender {
+            id,
+            ids: self.ids.clone(),
+            tx: self.tx.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let _ = self.tx.send((self.id, Message::Close));
+    }
+}
+
+impl<T> Iterator for Receiver<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        loop {
+            let current = *self.stack.last()?;
+            let message = match self.queues.get_mut(&current).and_then(VecDeque::pop_front) {
+                Some(message) => message,
+                None => {
+                    // wait for the stream being delivered
+                    let (id, message) = self.rx.recv().ok()?;
+                    if id != current {
+                        self.queues.entry(id).or_default().push_back(message);
+                        continue;
+                    }
+                    message
+                }
+            };
+
+            match message {
+                Message::Item(item) => return Some(item),
+                Message::Open(id) => self.stack.push(id),
+                Message::Close => {
+                    self.queues.remove(&current);
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn deliver_in_order() {
+        let (root, receiver) = channel();
+        root.send(0);
+        let first = root.open();
+        let second = root.open();
+        root.send(5);
+        drop(root);
+
+        // the later stream is fed first
+        let producer = thread::spawn(move || {
+            second.send(3);
+            let nested = second.open();
+            second.send(4);
+            drop(second);
+            first.send(1);
+            nested.send(2);
+            // stream is over only once the nested one is
+            drop(first);
+        });
+
+        assert_eq!(receiver.collect::<Vec<_>>(), vec![0, 1, 3, 2, 4, 5]);
+        producer.join().unwrap();
+    }
+}
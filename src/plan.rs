@@ -0,0 +1,251 @@
+use crate::{
+    error::{AppError, AppErrorKind},
+    size::Size,
+    stats,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// Version of the plan file format.
+const VERSION: u32 = 1;
+
+/// Items to be removed, produced by scanning destinations.
+///
+/// A plan can be saved to a JSON file for review and applied later exactly as
+/// it was reviewed.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Plan {
+    pub version: u32,
+    pub entries: Vec<Entry>,
+}
+
+/// Items of a destination.
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Clone)]
+pub struct Entry {
+    pub destination: PathBuf,
+    #[serde(default)]
+    pub one_file_system: bool,
+    pub items: Vec<Item>,
+}
+
+/// Item to be removed along with its identity at planning time.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Item {
+    pub path: PathBuf,
+    pub inode: Option<u64>,
+    pub modified: Option<SystemTime>,
+}
+
+impl Plan {
+    pub fn new() -> Plan {
+        Plan {
+            version: VERSION,
+            entries: vec![],
+        }
+    }
+
+    /// Add entry, skipping items which are already (part of) a planned item,
+    /// and dropping planned items which are part of one of its items. Planned
+    /// items are never nested, so removing one never changes another.
+    pub fn add(&mut self, mut entry: Entry) {
+        // an item is part of another one if any of its ancestors (or itself)
+        // is that one
+        let planned: HashSet<&Path> = self.items().map(|item| item.path.as_path()).collect();
+        entry
+            .items
+            .retain(|item| !item.path.ancestors().any(|path| planned.contains(path)));
+
+        let added: HashSet<&Path> = entry.items.iter().map(|item| item.path.as_path()).collect();
+        for planned in &mut self.entries {
+            planned
+                .items
+                .retain(|item| !item.path.ancestors().any(|path| added.contains(path)));
+        }
+        self.entries.push(entry);
+    }
+
+    /// Planned items of every entry.
+    pub fn items(&self) -> impl Iterator<Item = &Item> {
+        self.entries.iter().flat_map(|entry| &entry.items)
+    }
+
+    /// Number of planned items.
+    pub fn len(&self) -> usize {
+        self.entries.iter().map(|entry| entry.items.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Keep only items for which `keep` returns true, entries are kept even
+    /// if they have no item left.
+    pub fn retain<F: FnMut(&Item) -> bool>(&mut self, mut keep: F) {
+        for entry in &mut self.entries {
+            entry.items.retain(&mut keep);
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> crate::Result<Plan> {
+        let json_data = fs::read_to_string(path)?;
+        let plan: Plan = serde_json::from_str(&json_data)?;
+        if plan.version > VERSION {
+            return Err(AppError::new(
+                AppErrorKind::Usage,
+                format!("plan file version {} is not supported", plan.version),
+            ));
+        }
+        Ok(plan)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> crate::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+impl Default for Plan {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Item {
+    pub fn new<P: Into<PathBuf>>(path: P) -> io::Result<Item> {
+        let path = path.into();
+        stats::stat();
+        let metadata = fs::symlink_metadata(&path)?;
+
+        Ok(Item {
+            inode: self::inode(&metadata),
+            modified: metadata.modified().ok(),
+            path,
+        })
+    }
+
+    /// Current size of the item and its content.
+    pub fn size(&self) -> Size {
+        Size::of(&self.path)
+    }
+
+    /// Check whether the item was replaced or modified since planning.
+    pub fn changed(&self) -> bool {
+        match Item::new(&self.path) {
+            Ok(current) => current != *self,
+            Err(_) => true,
+        }
+    }
+}
+
+#[cfg(unix)]
+fn inode(metadata: &fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.ino())
+}
+
+#[cfg(not(unix))]
+fn inode(_metadata: &fs::Metadata) -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skip_nested_items() {
+        let item = |path: &str| Item {
+            path: PathBuf::from(path),
+            inode: None,
+            modified: None,
+        };
+
+        let mut plan = Plan::new();
+        plan.add(Entry {
+            destination: PathBuf::from("/pool/rust"),
+            one_file_system: false,
+            items: vec![item("/pool/rust/proj/target")],
+        });
+        plan.add(Entry {
+            destination: PathBuf::from("/pool/rust"),
+            one_file_system: false,
+            items: vec![
+                item("/pool/rust/proj/target/app.log"),
+                item("/pool/rust/proj/target.log"),
+                item("/pool/rust/proj/app.log"),
+            ],
+        });
+
+        // only paths inside a planned item are part of it
+        assert_eq!(
+            plan.entries[1].items,
+            vec![
+                item("/pool/rust/proj/target.log"),
+                item("/pool/rust/proj/app.log")
+            ]
+        );
+        assert_eq!(plan.len(), 3);
+
+        plan.retain(|item| item.path.extension().is_none());
+        assert_eq!(
+            plan.items().collect::<Vec<_>>(),
+            vec![&item("/pool/rust/proj/target")]
+        );
+        assert_eq!(plan.entries.len(), 2);
+
+        // a folder planned after its content replaces it
+        plan.add(Entry {
+            destination: PathBuf::from("/pool/rust"),
+            one_file_system: false,
+            items: vec![item("/pool/rust/proj")],
+        });
+        assert_eq!(
+            plan.items().collect::<Vec<_>>(),
+            vec![&item("/pool/rust/proj")]
+        );
+    }
+
+    #[test]
+    fn save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("build")).unwrap();
+
+        let mut plan = Plan::new();
+        plan.add(Entry {
+            destination: dir.path().to_path_buf(),
+            one_file_system: true,
+            items: vec![Item::new(dir.path().join("build")).unwrap()],
+        });
+
+        let path = dir.path().join("plan.json");
+        plan.save(&path).unwrap();
+        assert_eq!(Plan::load(&path).unwrap(), plan);
+    }
+
+    #[test]
+    fn changed_item() {
+        let dir = tempfile::tempdir().unwrap();
+        let build = dir.path().join("build");
+        fs::create_dir(&build).unwrap();
+
+        let item = Item::new(&build).unwrap();
+        assert!(!item.changed());
+
+        // modified
+        fs::write(build.join("app"), "binary").unwrap();
+        fs::File::open(&build)
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH)
+            .unwrap();
+        assert!(item.changed());
+
+        // removed
+        fs::remove_dir_all(&build).unwrap();
+        assert!(item.changed());
+    }
+}
@@ -0,0 +1,113 @@
+//! Live counters of a run, redrawn in place on stderr.
+//!
+//! Progress is only shown on a terminal, other messages clear it before they
+//! are printed and it's drawn again on the next update.
+
+use crate::size::{Bytes, Size};
+use std::{
+    io::{self, Write},
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+/// Minimum time between two redraws.
+const INTERVAL: Duration = Duration::from_millis(100);
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static DRAWN: AtomicBool = AtomicBool::new(false);
+static SCANNED: AtomicU64 = AtomicU64::new(0);
+static MATCHED: AtomicU64 = AtomicU64::new(0);
+static QUEUED: AtomicU64 = AtomicU64::new(0);
+static FREED: AtomicU64 = AtomicU64::new(0);
+static LAST: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Show progress, callers decide whether stderr is a terminal.
+pub fn enable(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Directory scanned.
+pub fn scanned() {
+    SCANNED.fetch_add(1, Ordering::Relaxed);
+    self::update();
+}
+
+/// Item matched with patterns.
+pub fn matched() {
+    MATCHED.fetch_add(1, Ordering::Relaxed);
+    self::update();
+}
+
+/// Item about to be removed.
+pub fn queued(size: Size) {
+    QUEUED.fetch_add(size.disk, Ordering::Relaxed);
+    self::update();
+}
+
+/// Item removed.
+pub fn freed(size: Size) {
+    FREED.fetch_add(size.disk, Ordering::Relaxed);
+    self::update();
+}
+
+/// Clear progress line, before printing anything else.
+pub fn clear() {
+    if DRAWN.swap(false, Ordering::Relaxed) {
+        let mut stderr = io::stderr().lock();
+        let _ = write!(stderr, "\r\u{1b}[2K");
+        let _ = stderr.flush();
+    }
+}
+
+fn update() {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+
+    // another thread is already redrawing
+    let Ok(mut last) = LAST.try_lock() else {
+        return;
+    };
+    if last.is_some_and(|last| last.elapsed() < INTERVAL) {
+        return;
+    }
+    *last = Some(Instant::now());
+
+    let line = self::line(
+        SCANNED.load(Ordering::Relaxed),
+        MATCHED.load(Ordering::Relaxed),
+        QUEUED.load(Ordering::Relaxed),
+        FREED.load(Ordering::Relaxed),
+    );
+    let mut stderr = io::stderr().lock();
+    // a closed stderr must not stop the run
+    let _ = write!(stderr, "\r\u{1b}[2K{}", line);
+    let _ = stderr.flush();
+    DRAWN.store(true, Ordering::Relaxed);
+}
+
+fn line(scanned: u64, matched: u64, queued: u64, freed: u64) -> String {
+    format!(
+        "Scanned {} directories, {} matches, {} queued, {} freed",
+        scanned,
+        matched,
+        Bytes(queued),
+        Bytes(freed)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_line() {
+        assert_eq!(
+            line(1200, 3, 3 * 1024 * 1024, 1024),
+            "Scanned 1200 directories, 3 matches, 3.0 MiB queued, 1.0 KiB freed"
+        );
+    }
+}
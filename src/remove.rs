@@ -0,0 +1,705 @@
+//! Removal core which never escapes the destination.
+//!
+//! Items are matched by path and removed later, so any component of that path
+//! could be swapped for a symbolic link in between. On Unix-like systems every
+//! step is done relative to an open directory (`openat`/`unlinkat` with
+//! `O_NOFOLLOW`), so a swapped-in symbolic link is removed itself or refused,
+//! but never followed.
+//!
+//! Content of a folder is removed concurrently: its sub-folders on separate
+//! threads, and files of a big folder in chunks. A folder can also be detached
+//! instead, renamed aside in one step so it can be removed later, e.g. by a
+//! background process.
+
+use crate::size::Size;
+use std::{
+    ffi::OsStr,
+    io::{Error, ErrorKind, Result},
+    path::{Component, Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// Prefix of detached folders, they are never searched.
+const TRASH: &str = ".neaten-trash-";
+
+/// Detached folders of this process, to name them uniquely.
+static DETACHED: AtomicU64 = AtomicU64::new(0);
+
+/// What `detach` did with an item.
+#[derive(Debug, PartialEq)]
+pub enum Detached {
+    /// file or symbolic link, removed right away, freeing this space.
+    Removed(Size),
+    /// folder renamed aside to this path.
+    Renamed(PathBuf),
+}
+
+/// Remove `path` (file, symbolic link or folder with its content) which lives
+/// under `root`, and return the space freed.
+///
+/// If `device` is provided, folders on another device are not removed.
+pub fn remove<R: AsRef<Path>, P: AsRef<Path>>(
+    root: R,
+    path: P,
+    device: Option<u64>,
+) -> Result<Size> {
+    let root = root.as_ref();
+    let (parents, name) = self::split(root, path.as_ref())?;
+    imp::remove(root, &parents, name, device)
+}
+
+/// Rename folder `path` which lives under `root` aside, to remove it later, and
+/// return its new path. Files and symbolic links are removed right away, as
+/// they are quick to remove.
+///
+/// The new name carries the pid of `owner`, the process which removes it, so
+/// that it's reclaimed if that process dies first. If `device` is provided, a
+/// folder on another device is not detached.
+pub fn detach<R: AsRef<Path>, P: AsRef<Path>>(
+    root: R,
+    path: P,
+    device: Option<u64>,
+    owner: u32,
+) -> Result<Detached> {
+    let root = root.as_ref();
+    let path = path.as_ref();
+    let (parents, name) = self::split(root, path)?;
+
+    let trash = format!(
+        "{}{}-{}",
+        TRASH,
+        owner,
+        DETACHED.fetch_add(1, Ordering::Relaxed)
+    );
+    let removed = imp::detach(root, &parents, name, OsStr::new(&trash), device)?;
+    Ok(match removed {
+        Some(size) => Detached::Removed(size),
+        None => Detached::Renamed(path.with_file_name(trash)),
+    })
+}
+
+/// Whether `name` is a detached folder, which may be removed at any time.
+pub fn is_trash(name: &str) -> bool {
+    name.starts_with(TRASH)
+}
+
+/// Whether `name` is a detached folder whose owner is gone, so nobody will
+/// ever remove it.
+pub fn is_stale(name: &str) -> bool {
+    let owner = name
+        .strip_prefix(TRASH)
+        .and_then(|rest| rest.split('-').next())
+        .and_then(|pid| pid.parse::<i32>().ok());
+    match owner {
+        Some(pid) if pid > 0 => !imp::alive(pid),
+        _ => false,
+    }
+}
+
+/// Components of `path` relative to `root`: its parents and its name.
+fn split<'a>(root: &Path, path: &'a Path) -> Result<(Vec<&'a OsStr>, &'a OsStr)> {
+    // path relative to root, without any `..` or absolute component
+    let relative = path.strip_prefix(root).map_err(|_| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("{:?} is outside of {:?}", path, root),
+        )
+    })?;
+    let mut names = Vec::new();
+    for component in relative.components() {
+        match component {
+            Component::Normal(name) => names.push(name),
+            Component::CurDir => {}
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("{:?} is outside of {:?}", path, root),
+                ));
+            }
+        }
+    }
+    let name = names.pop().ok_or(Error::new(
+        ErrorKind::InvalidInput,
+        format!("refusing to remove {:?} itself", root),
+    ))?;
+    Ok((names, name))
+}
+
+#[cfg(unix)]
+mod imp {
+    use super::*;
+    use crate::{progress, throttle};
+    use rayon::prelude::*;
+    use std::{
+        ffi::{CStr, CString},
+        os::{
+            fd::{AsRawFd, FromRawFd, OwnedFd},
+            unix::ffi::OsStrExt,
+        },
+    };
+
+    /// Entries of a folder handled by a single thread, bigger folders are
+    /// split across threads.
+    const CHUNK: usize = 64;
+
+    pub fn remove(
+        root: &Path,
+        parents: &[&OsStr],
+        name: &OsStr,
+        device: Option<u64>,
+    ) -> Result<Size> {
+        let parent = self::parent(root, parents)?;
+        let name = self::c_name(name)?;
+        let (is_dir, size) = self::measure(&parent, &name)?;
+        if !is_dir {
+            // file or symbolic link, remove the entry itself
+            self::unlink(&parent, &name, 0, size)?;
+            return Ok(size);
+        }
+
+        // folder, its sub-folders are removed concurrently
+        let (dir, frame) = self::frame(&parent, &name, size, device)?;
+        let content = frame
+            .entries
+            .par_iter()
+            .with_max_len(1)
+            .map(|(child, size)| self::remove_dir(&dir, child, *size, device))
+            .try_reduce(Size::default, |a, b| Ok(a + b))?;
+        drop(dir);
+
+        self::unlink(&parent, &name, libc::AT_REMOVEDIR, size)?;
+        Ok(size + frame.removed + content)
+    }
+
+    pub fn detach(
+        root: &Path,
+        parents: &[&OsStr],
+        name: &OsStr,
+        trash: &OsStr,
+        device: Option<u64>,
+    ) -> Result<Option<Size>> {
+        let parent = self::parent(root, parents)?;
+        let name = self::c_name(name)?;
+        let (is_dir, size) = self::measure(&parent, &name)?;
+        if !is_dir {
+            self::unlink(&parent, &name, 0, size)?;
+            return Ok(Some(size));
+        }
+        if let Some(device) = device
+            && self::device(&self::open_dir(&parent, &name)?)? != device
+        {
+            return Err(Error::other(format!(
+                "skipping {:?}, since it's on a different device",
+                name
+            )));
+        }
+
+        // never replace an existing entry
+        let trash = self::c_name(trash)?;
+        if self::stat(&parent, &trash).is_ok() {
+            return Err(Error::new(
+                ErrorKind::AlreadyExists,
+                format!("{:?} already exists", trash),
+            ));
+        }
+        self::check(unsafe {
+            libc::renameat(
+                parent.as_raw_fd(),
+                name.as_ptr(),
+                parent.as_raw_fd(),
+                trash.as_ptr(),
+            )
+        })?;
+        Ok(None)
+    }
+
+    /// Whether process `pid` exists, even if it belongs to another user.
+    pub fn alive(pid: i32) -> bool {
+        let signaled = unsafe { libc::kill(pid, 0) } == 0;
+        signaled || Error::last_os_error().raw_os_error() != Some(libc::ESRCH)
+    }
+
+    /// Open `root`, then walk down `parents`, refusing symbolic links.
+    fn parent(root: &Path, parents: &[&OsStr]) -> Result<OwnedFd> {
+        let root = CString::new(root.as_os_str().as_bytes())?;
+        let mut dir = self::fd(unsafe {
+            libc::open(
+                root.as_ptr(),
+                libc::O_RDONLY | libc::O_DIRECTORY | libc::O_CLOEXEC,
+            )
+        })?;
+        for parent in parents {
+            dir = self::open_dir(&dir, &self::c_name(parent)?)?;
+        }
+        Ok(dir)
+    }
+
+    /// Folder being emptied, along with its sub-folders left to remove.
+    struct Frame {
+        /// device and inode, to check it when it's opened again.
+        id: (u64, u64),
+        name: CString,
+        /// space of the folder entry itself.
+        size: Size,
+        /// space of its files, already removed.
+        removed: Size,
+        entries: Vec<(CString, Size)>,
+    }
+
+    /// Remove folder `name` of `size` with an explicit stack, so a deep tree
+    /// can't overflow the call stack, and return the space freed.
+    ///
+    /// Only the folder being emptied is kept open, so a deep tree can't run
+    /// out of file descriptors either: its parent is opened again through
+    /// `..` once it's empty.
+    fn remove_dir(parent: &OwnedFd, name: &CStr, size: Size, device: Option<u64>) -> Result<Size> {
+        let (mut dir, frame) = self::frame(parent, name, size, device)?;
+        let mut stack = vec![frame];
+        let mut freed = Size::default();
+        while let Some(frame) = stack.last_mut() {
+            if let Some((child, size)) = frame.entries.pop() {
+                let (child, frame) = self::frame(&dir, &child, size, device)?;
+                dir = child;
+                stack.push(frame);
+                continue;
+            }
+
+            let Some(Frame {
+                name,
+                size,
+                removed,
+                ..
+            }) = stack.pop()
+            else {
+                break;
+            };
+            freed += size + removed;
+            let Some(up) = stack.last() else {
+                drop(dir);
+                self::unlink(parent, &name, libc::AT_REMOVEDIR, size)?;
+                break;
+            };
+            dir = self::reopen_parent(&dir, up.id)?;
+            self::unlink(&dir, &name, libc::AT_REMOVEDIR, size)?;
+        }
+        Ok(freed)
+    }
+
+    /// Open the parent of `dir` again, refusing another folder than `id`, e.g.
+    /// if `dir` was moved meanwhile.
+    fn reopen_parent(dir: &OwnedFd, id: (u64, u64)) -> Result<OwnedFd> {
+        let parent = self::open_dir(dir, c"..")?;
+        if self::id(&parent)? != id {
+            return Err(Error::other(
+                "folder was moved while its content was being removed",
+            ));
+        }
+        Ok(parent)
+    }
+
+    /// Open folder `name` of `size` and remove its files, its sub-folders are
+    /// left.
+    fn frame(
+        parent: &OwnedFd,
+        name: &CStr,
+        size: Size,
+        device: Option<u64>,
+    ) -> Result<(OwnedFd, Frame)> {
+        let dir = self::open_dir(parent, name)?;
+        let id = self::id(&dir)?;
+        if let Some(device) = device
+            && id.0 != device
+        {
+            return Err(Error::other(format!(
+                "skipping {:?}, since it's on a different device",
+                name
+            )));
+        }
+
+        let measured = self::entries(&dir)?
+            .into_par_iter()
+            .with_min_len(CHUNK)
+            .map(|entry| {
+                let (is_dir, size) = self::measure(&dir, &entry)?;
+                if !is_dir {
+                    self::unlink(&dir, &entry, 0, size)?;
+                }
+                Ok((entry, is_dir, size))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut removed = Size::default();
+        let mut entries = vec![];
+        for (entry, is_dir, size) in measured {
+            if is_dir {
+                entries.push((entry, size));
+            } else {
+                removed += size;
+            }
+        }
+        let frame = Frame {
+            id,
+            name: name.to_owned(),
+            size,
+            removed,
+            entries,
+        };
+        Ok((dir, frame))
+    }
+
+    /// Whether entry `name` is a folder, and its size, queued for removal.
+    fn measure(parent: &OwnedFd, name: &CStr) -> Result<(bool, Size)> {
+        let (is_dir, size) = self::stat(parent, name)?;
+        progress::queued(size);
+        Ok((is_dir, size))
+    }
+
+    /// Whether entry `name` is a folder, and its size.
+    fn stat(parent: &OwnedFd, name: &CStr) -> Result<(bool, Size)> {
+        let mut stat: libc::stat = unsafe { std::mem::zeroed() };
+        self::check(unsafe {
+            libc::fstatat(
+                parent.as_raw_fd(),
+                name.as_ptr(),
+                &mut stat,
+                libc::AT_SYMLINK_NOFOLLOW,
+            )
+        })?;
+        let is_dir = (stat.st_mode & libc::S_IFMT) == libc::S_IFDIR;
+        let size = Size {
+            apparent: stat.st_size as u64,
+            // `st_blocks` is always in 512-byte units
+            disk: stat.st_blocks as u64 * 512,
+        };
+        Ok((is_dir, size))
+    }
+
+    /// Remove entry `name` of `size`, at the pace of `--rate-limit`.
+    fn unlink(parent: &OwnedFd, name: &CStr, flags: libc::c_int, size: Size) -> Result<()> {
+        throttle::take(1, size.disk);
+        self::check(unsafe { libc::unlinkat(parent.as_raw_fd(), name.as_ptr(), flags) })?;
+        progress::freed(size);
+        Ok(())
+    }
+
+    fn open_dir(parent: &OwnedFd, name: &CStr) -> Result<OwnedFd> {
+        self::fd(unsafe {
+            libc::openat(
+                parent.as_raw_fd(),
+                name.as_ptr(),
+                libc::O_RDONLY | libc::O_DIRECTORY | libc::O_NOFOLLOW | libc::O_CLOEXEC,
+            )
+        })
+    }
+
+    fn entries(dir: &OwnedFd) -> Result<Vec<CString>> {
+        // `fdopendir` takes ownership of the descriptor
+        let fd = self::fd(unsafe { libc::dup(dir.as_raw_fd()) })?;
+        let stream = unsafe { libc::fdopendir(fd.as_raw_fd()) };
+        if stream.is_null() {
+            return Err(Error::last_os_error());
+        }
+        std::mem::forget(fd);
+
+        let mut entries = Vec::new();
+        loop {
+            let entry = unsafe { libc::readdir(stream) };
+            if entry.is_null() {
+                break;
+            }
+            let name = unsafe { CStr::from_ptr((*entry).d_name.as_ptr()) };
+            if name.to_bytes() != b"." && name.to_bytes() != b".." {
+                entries.push(name.to_owned());
+            }
+        }
+        unsafe { libc::closedir(stream) };
+
+        Ok(entries)
+    }
+
+    fn device(dir: &OwnedFd) -> Result<u64> {
+        Ok(self::id(dir)?.0)
+    }
+
+    /// Device and inode of `dir`.
+    fn id(dir: &OwnedFd) -> Result<(u64, u64)> {
+        let mut stat: libc::stat = unsafe { std::mem::zeroed() };
+        self::check(unsafe { libc::fstat(dir.as_raw_fd(), &mut stat) })?;
+        Ok((stat.st_dev as u64, stat.st_ino as u64))
+    }
+
+    fn c_name(name: &OsStr) -> Result<CString> {
+        Ok(CString::new(name.as_bytes())?)
+    }
+
+    fn fd(fd: libc::c_int) -> Result<OwnedFd> {
+        if fd < 0 {
+            Err(Error::last_os_error())
+        } else {
+            Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+        }
+    }
+
+    fn check(ret: libc::c_int) -> Result<()> {
+        if ret < 0 {
+            Err(Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+// best effort: directory handles are not available, so check every component
+// right before removing it.
+#[cfg(not(unix))]
+mod imp {
+    use super::*;
+    use crate::{progress, throttle};
+    use std::fs;
+
+    pub fn remove(
+        root: &Path,
+        parents: &[&OsStr],
+        name: &OsStr,
+        _device: Option<u64>,
+    ) -> Result<Size> {
+        let path = self::path(root, parents, name)?;
+        let metadata = fs::symlink_metadata(&path)?;
+        // removed at once, so it's accounted as a whole
+        let size = Size::of(&path);
+        progress::queued(size);
+        throttle::take(1, size.disk);
+        if metadata.is_dir() {
+            // doesn't follow symbolic links inside the folder
+            fs::remove_dir_all(&path)?;
+        } else if metadata.is_symlink() {
+            fs::remove_file(&path).or_else(|_| fs::remove_dir(&path))?;
+        } else {
+            fs::remove_file(&path)?;
+        }
+        progress::freed(size);
+        Ok(size)
+    }
+
+    pub fn detach(
+        root: &Path,
+        parents: &[&OsStr],
+        name: &OsStr,
+        trash: &OsStr,
+        device: Option<u64>,
+    ) -> Result<Option<Size>> {
+        let path = self::path(root, parents, name)?;
+        if !fs::symlink_metadata(&path)?.is_dir() {
+            return self::remove(root, parents, name, device).map(Some);
+        }
+        let trash = path.with_file_name(trash);
+        if fs::symlink_metadata(&trash).is_ok() {
+            return Err(Error::new(
+                ErrorKind::AlreadyExists,
+                format!("{:?} already exists", trash),
+            ));
+        }
+        fs::rename(&path, &trash)?;
+        Ok(None)
+    }
+
+    /// Liveness of other processes is unknown, so detached folders are kept.
+    pub fn alive(_pid: i32) -> bool {
+        true
+    }
+
+    fn path(root: &Path, parents: &[&OsStr], name: &OsStr) -> Result<PathBuf> {
+        let mut path = root.to_path_buf();
+        for parent in parents {
+            path.push(parent);
+            if fs::symlink_metadata(&path)?.is_symlink() {
+                return Err(Error::other(format!(
+                    "refusing to follow symbolic link {:?}",
+                    path
+                )));
+            }
+        }
+        path.push(name);
+        Ok(path)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::{fs, os::unix::fs::symlink, process};
+
+    #[test]
+    fn remove_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let build = dir.path().join("proj").join("build");
+        fs::create_dir_all(build.join("debug")).unwrap();
+        fs::write(build.join("debug").join("app"), "binary").unwrap();
+        fs::write(build.join(".hidden"), "hidden").unwrap();
+
+        // space freed is measured along the way
+        let size = Size::of(&build);
+        assert_eq!(remove(dir.path(), &build, None).unwrap(), size);
+        assert!(!build.exists());
+        assert!(dir.path().join("proj").exists());
+    }
+
+    #[test]
+    fn remove_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("app.log");
+        fs::write(&log, "log").unwrap();
+
+        remove(dir.path(), &log, None).unwrap();
+        assert!(!log.exists());
+    }
+
+    #[test]
+    fn outside_of_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let other = tempfile::tempdir().unwrap();
+
+        assert!(remove(dir.path(), other.path(), None).is_err());
+        assert!(remove(dir.path(), dir.path(), None).is_err());
+        assert!(remove(dir.path(), dir.path().join("..").join("x"), None).is_err());
+        assert!(other.path().exists());
+    }
+
+    #[test]
+    fn swapped_parent() {
+        let dir = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        fs::create_dir(outside.path().join("build")).unwrap();
+
+        // `proj/build` was matched, then `proj` is swapped for a symbolic link
+        let proj = dir.path().join("proj");
+        fs::create_dir_all(proj.join("build")).unwrap();
+        fs::remove_dir_all(&proj).unwrap();
+        symlink(outside.path(), &proj).unwrap();
+
+        assert!(remove(dir.path(), proj.join("build"), None).is_err());
+        assert!(outside.path().join("build").exists());
+    }
+
+    #[test]
+    fn swapped_item() {
+        let dir = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        fs::write(outside.path().join("important"), "data").unwrap();
+
+        // `build` was matched, then swapped for a symbolic link
+        let build = dir.path().join("build");
+        symlink(outside.path(), &build).unwrap();
+
+        remove(dir.path(), &build, None).unwrap();
+        assert!(fs::symlink_metadata(&build).is_err());
+        assert!(outside.path().join("important").exists());
+    }
+
+    #[test]
+    fn symlink_inside_item() {
+        let dir = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        fs::write(outside.path().join("important"), "data").unwrap();
+
+        let build = dir.path().join("build");
+        fs::create_dir(&build).unwrap();
+        symlink(outside.path(), build.join("link")).unwrap();
+
+        remove(dir.path(), &build, None).unwrap();
+        assert!(!build.exists());
+        assert!(outside.path().join("important").exists());
+    }
+
+    #[test]
+    fn deep_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let build = dir.path().join("build");
+        let mut path = build.clone();
+        for _ in 0..500 {
+            path.push("d");
+        }
+        fs::create_dir_all(&path).unwrap();
+        fs::write(path.join("app"), "binary").unwrap();
+
+        // deeper than a recursive removal could go on a small stack
+        let root = dir.path().to_path_buf();
+        std::thread::Builder::new()
+            .stack_size(64 * 1024)
+            .spawn({
+                let build = build.clone();
+                move || remove(root, build, None)
+            })
+            .unwrap()
+            .join()
+            .unwrap()
+            .unwrap();
+        assert!(!build.exists());
+    }
+
+    #[test]
+    fn remove_big_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let modules = dir.path().join("node_modules");
+        for package in 0..20 {
+            let lib = modules.join(format!("pkg{}", package)).join("lib");
+            fs::create_dir_all(&lib).unwrap();
+            for file in 0..100 {
+                fs::write(lib.join(format!("file{}.js", file)), "").unwrap();
+            }
+        }
+        fs::write(modules.join(".package-lock.json"), "{}").unwrap();
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(4)
+            .build()
+            .unwrap();
+        pool.install(|| remove(dir.path(), &modules, None)).unwrap();
+        assert!(!modules.exists());
+    }
+
+    #[test]
+    fn detach_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let build = dir.path().join("proj").join("build");
+        fs::create_dir_all(build.join("debug")).unwrap();
+        let log = dir.path().join("proj").join("app.log");
+        fs::write(&log, "log").unwrap();
+
+        // folder is renamed aside, within its parent
+        let Detached::Renamed(trash) = detach(dir.path(), &build, None, process::id()).unwrap()
+        else {
+            panic!("{:?} wasn't renamed", build);
+        };
+        assert!(!build.exists());
+        assert_eq!(trash.parent(), build.parent());
+        let name = trash.file_name().unwrap().to_str().unwrap();
+        assert!(is_trash(name));
+        assert!(!is_stale(name));
+        assert!(trash.join("debug").exists());
+
+        // file is removed right away
+        let size = Size::of(&log);
+        assert_eq!(
+            detach(dir.path(), &log, None, process::id()).unwrap(),
+            Detached::Removed(size)
+        );
+        assert!(!log.exists());
+
+        remove(dir.path(), &trash, None).unwrap();
+        assert!(!trash.exists());
+    }
+
+    #[test]
+    fn stale_trash() {
+        // owner which is gone
+        let mut child = process::Command::new("true").spawn().unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+
+        assert!(is_stale(&format!("{}{}-0", TRASH, pid)));
+        assert!(!is_stale(&format!("{}{}-0", TRASH, process::id())));
+        assert!(!is_stale(&format!("{}unknown", TRASH)));
+        assert!(!is_stale("target"));
+    }
+}
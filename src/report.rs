@@ -0,0 +1,402 @@
+use crate::{
+    event::{self, Event},
+    log,
+    size::{Bytes, Size},
+    theme::{self, Style},
+};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Output format of a run.
+#[derive(Deserialize, Default, Debug, PartialEq, Clone, Copy, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// colored messages and a summary table.
+    #[default]
+    Human,
+    /// one JSON report on stdout, messages on stderr.
+    Json,
+    /// one JSON event per line on stdout, messages on stderr.
+    Ndjson,
+}
+
+/// Outcome of a run, per config entry and overall.
+#[derive(Serialize, Default, Debug, PartialEq)]
+pub struct Report {
+    pub dryrun: bool,
+    pub entries: Vec<EntryReport>,
+    pub duration_ms: u64,
+}
+
+/// Outcome of a config entry.
+#[derive(Serialize, Default, Debug, PartialEq)]
+pub struct EntryReport {
+    pub destination: PathBuf,
+    /// matched items and what happened to them.
+    pub items: Vec<ItemReport>,
+    /// paths ignored because of `exclude`.
+    pub excluded: Vec<PathBuf>,
+    pub errors: Vec<String>,
+    /// number of removed (or to be removed in dry-run) items.
+    pub removed: usize,
+    /// space freed (or to be freed in dry-run) by removed items.
+    pub size: Size,
+    /// number of detached folders, still in use until the background process
+    /// removes them.
+    pub detached: usize,
+    pub duration_ms: u64,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ItemReport {
+    pub path: PathBuf,
+    pub status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<Size>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+#[derive(Serialize, Debug, PartialEq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Removed,
+    /// renamed aside, removed by a background process.
+    Detached,
+    WouldRemove,
+    Skipped,
+    Failed,
+}
+
+impl Report {
+    pub fn new(dryrun: bool) -> Report {
+        Report {
+            dryrun,
+            ..Default::default()
+        }
+    }
+
+    pub fn removed(&self) -> usize {
+        self.entries.iter().map(|entry| entry.removed).sum()
+    }
+
+    pub fn size(&self) -> Size {
+        self.entries
+            .iter()
+            .fold(Size::default(), |size, entry| size + entry.size)
+    }
+
+    pub fn detached(&self) -> usize {
+        self.entries.iter().map(|entry| entry.detached).sum()
+    }
+
+    pub fn errors(&self) -> usize {
+        self.entries.iter().map(|entry| entry.errors.len()).sum()
+    }
+
+    pub fn finish(&mut self, duration: Duration) {
+        self.duration_ms = duration.as_millis() as u64;
+        event::emit(Event::RunFinished {
+            dryrun: self.dryrun,
+            removed: self.removed(),
+            size: self.size(),
+            detached: self.detached(),
+            errors: self.errors(),
+            duration_ms: self.duration_ms,
+        });
+    }
+
+    /// Summary table of the run.
+    pub fn summary(&self) -> String {
+        let rows: Vec<(String, usize, Size)> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.destination.display().to_string(),
+                    entry.removed,
+                    entry.size,
+                )
+            })
+            .chain([(String::from("Total"), self.removed(), self.size())])
+            .collect();
+        let width = rows
+            .iter()
+            .map(|(name, ..)| name.chars().count())
+            .max()
+            .unwrap_or_default()
+            .max("Destination".len());
+
+        let mut msg = format!("\n{}\n", theme::paint(Style::Bold, "Summary"));
+        msg = format!(
+            "{}{:<width$}  {:>8}  {:>12}  {:>12}\n",
+            msg, "Destination", "Items", "Apparent", "On disk"
+        );
+        for (name, removed, size) in &rows {
+            msg = format!(
+                "{}{:<width$}  {:>8}  {:>12}  {:>12}\n",
+                msg,
+                name,
+                removed,
+                Bytes(size.apparent).to_string(),
+                Bytes(size.disk).to_string()
+            );
+        }
+
+        let size = self.size();
+        msg = format!(
+            "{}\n{} {} ({} apparent)",
+            msg,
+            if self.dryrun { "Would free" } else { "Freed" },
+            Bytes(size.disk),
+            Bytes(size.apparent)
+        );
+        let detached = self.detached();
+        if detached > 0 {
+            msg = format!("{}, {} folders being freed in background", msg, detached);
+        }
+        msg
+    }
+}
+
+impl EntryReport {
+    pub fn new<P: Into<PathBuf>>(destination: P) -> EntryReport {
+        EntryReport {
+            destination: destination.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn scan_started(&self) {
+        event::emit(Event::ScanStarted {
+            destination: &self.destination,
+        });
+    }
+
+    pub fn exclude<P: Into<PathBuf>>(&mut self, path: P) {
+        let path = path.into();
+        log::verbose(format!(
+            "{} {:?}...",
+            theme::paint(Style::Warning, "Exclude"),
+            path
+        ));
+        event::emit(Event::Excluded { path: &path });
+        self.excluded.push(path);
+    }
+
+    /// Item which is planned to be removed.
+    pub fn matched<P: AsRef<Path>>(&self, path: P) {
+        event::emit(Event::Matched {
+            path: path.as_ref(),
+        });
+    }
+
+    /// Matched item which is kept, e.g. it's protected or changed since planning.
+    pub fn skip<P: Into<PathBuf>, R: Display>(&mut self, path: P, reason: R) {
+        let path = path.into();
+        let reason = reason.to_string();
+        log::info(format!(
+            "{} {:?}... {}",
+            theme::paint(Style::Warning, "Skip"),
+            path,
+            reason
+        ));
+        event::emit(Event::Skipped {
+            path: &path,
+            reason: &reason,
+        });
+        self.push(path, Status::Skipped, None, Some(reason));
+    }
+
+    pub fn removing<P: Into<PathBuf>>(&mut self, path: P) {
+        log::verbose(format!(
+            "{} {:?}...",
+            theme::paint(Style::Removing, "Removing"),
+            path.into()
+        ));
+    }
+
+    pub fn would_remove<P: Into<PathBuf>>(&mut self, path: P, size: Size) {
+        let path = path.into();
+        log::info(format!(
+            "{} {:?}...",
+            theme::paint(Style::Removing, "Would remove"),
+            path
+        ));
+        event::emit(Event::WouldRemove { path: &path, size });
+        self.removed += 1;
+        self.size += size;
+        self.push(path, Status::WouldRemove, Some(size), None);
+    }
+
+    pub fn removed<P: Into<PathBuf>>(&mut self, path: P, size: Size) {
+        let path = path.into();
+        log::info(format!(
+            "{} {:?}...",
+            theme::paint(Style::Removed, "Removed"),
+            path
+        ));
+        event::emit(Event::Removed { path: &path, size });
+        self.removed += 1;
+        self.size += size;
+        self.push(path, Status::Removed, Some(size), None);
+    }
+
+    /// Folder renamed aside, its space is freed (and measured) once the
+    /// background process removed it.
+    pub fn detached<P: Into<PathBuf>>(&mut self, path: P) {
+        let path = path.into();
+        log::info(format!(
+            "{} {:?}...",
+            theme::paint(Style::Removed, "Detached"),
+            path
+        ));
+        event::emit(Event::Detached { path: &path });
+        self.removed += 1;
+        self.detached += 1;
+        self.push(path, Status::Detached, None, None);
+    }
+
+    pub fn failed<P: Into<PathBuf>, E: Display>(&mut self, path: P, error: E) {
+        let path = path.into();
+        let error = error.to_string();
+        log::error(&error);
+        event::emit(Event::Error {
+            path: Some(&path),
+            message: &error,
+        });
+        self.errors.push(error.clone());
+        self.push(path, Status::Failed, None, Some(error));
+    }
+
+    pub fn error<E: Display>(&mut self, error: E) {
+        let error = error.to_string();
+        log::error(&error);
+        event::emit(Event::Error {
+            path: None,
+            message: &error,
+        });
+        self.errors.push(error);
+    }
+
+    pub fn elapsed(&mut self, duration: Duration) {
+        self.duration_ms += duration.as_millis() as u64;
+    }
+
+    pub fn finish(&self) {
+        event::emit(Event::EntryFinished {
+            destination: &self.destination,
+            removed: self.removed,
+            size: self.size,
+            detached: self.detached,
+            errors: self.errors.len(),
+            duration_ms: self.duration_ms,
+        });
+    }
+
+    fn push<P: Into<PathBuf>>(
+        &mut self,
+        path: P,
+        status: Status,
+        size: Option<Size>,
+        reason: Option<String>,
+    ) {
+        self.items.push(ItemReport {
+            path: path.into(),
+            status,
+            size,
+            reason,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary() {
+        let mut report = Report::new(true);
+        let mut entry = EntryReport::new("/pool/rust");
+        entry.would_remove(
+            "/pool/rust/proj/target",
+            Size {
+                apparent: 1024,
+                disk: 4096,
+            },
+        );
+        report.entries.push(entry);
+        let mut entry = EntryReport::new("/pool/node");
+        entry.would_remove(
+            "/pool/node/proj/node_modules",
+            Size {
+                apparent: 1024 * 1024 * 1024,
+                disk: 1024 * 1024 * 1024,
+            },
+        );
+        report.entries.push(entry);
+
+        assert_eq!(report.removed(), 2);
+        assert_eq!(report.size().disk, 1024 * 1024 * 1024 + 4096);
+
+        let summary = report.summary();
+        assert!(summary.contains("/pool/rust"));
+        assert!(summary.contains("Total"));
+        assert!(summary.contains("Would free 1.0 GiB (1.0 GiB apparent)"));
+    }
+
+    #[test]
+    fn summary_detached() {
+        let mut report = Report::new(false);
+        let mut entry = EntryReport::new("/pool/node");
+        let size = Size {
+            apparent: 1024,
+            disk: 4096,
+        };
+        entry.removed("/pool/node/app.log", size);
+        entry.detached("/pool/node/proj/node_modules");
+        report.entries.push(entry);
+
+        assert_eq!(report.removed(), 2);
+        assert_eq!(report.size(), size);
+        assert_eq!(report.detached(), 1);
+        assert!(
+            report
+                .summary()
+                .ends_with("Freed 4.0 KiB (1.0 KiB apparent), 1 folders being freed in background")
+        );
+    }
+
+    #[test]
+    fn json() {
+        let mut report = Report::new(false);
+        let mut entry = EntryReport::new("/pool/rust");
+        entry.exclude("/pool/rust/vendor");
+        entry.removed(
+            "/pool/rust/proj/target",
+            Size {
+                apparent: 10,
+                disk: 4096,
+            },
+        );
+        entry.skip("/pool/rust/lib/target", "contains tracked files");
+        entry.failed("/pool/rust/app/target", "permission denied");
+        report.entries.push(entry);
+
+        let json: serde_json::Value = serde_json::to_value(&report).unwrap();
+        let entry = &json["entries"][0];
+        assert_eq!(entry["excluded"][0], "/pool/rust/vendor");
+        assert_eq!(entry["items"][0]["status"], "removed");
+        assert_eq!(entry["items"][0]["size"]["disk"], 4096);
+        assert_eq!(entry["items"][1]["status"], "skipped");
+        assert_eq!(entry["items"][1]["reason"], "contains tracked files");
+        assert_eq!(entry["items"][2]["status"], "failed");
+        assert_eq!(entry["errors"][0], "permission denied");
+        assert_eq!(entry["removed"], 1);
+    }
+}
@@ -0,0 +1,122 @@
+use crate::{
+    Config,
+    manager::helper::{self, Options},
+    plan::Plan,
+    report::{EntryReport, Report},
+};
+use std::time::Instant;
+
+/// Finds items to be removed in destinations of config entries, without
+/// removing anything.
+///
+/// The resulting [`Plan`] can be inspected and filtered before an
+/// [`Executor`](crate::Executor) applies it. Nothing is printed, what was
+/// excluded, skipped or failed is in the [`Report`].
+///
+/// ```no_run
+/// use neaten::{Config, Kind, Scanner};
+///
+/// let configs = vec![Config::new("/usr/sample/rust", Kind::Folder, vec!["target"], None)];
+/// let (plan, report) = Scanner::new(&configs).scan();
+/// for item in plan.items() {
+///     println!("{}", item.path.display());
+/// }
+/// println!("{} errors", report.errors());
+/// ```
+#[derive(Debug)]
+pub struct Scanner<'a> {
+    configs: &'a [Config],
+    force: bool,
+}
+
+impl<'a> Scanner<'a> {
+    pub fn new(configs: &'a [Config]) -> Scanner<'a> {
+        Scanner {
+            configs,
+            force: false,
+        }
+    }
+
+    /// Plan items even if they hold files tracked by git.
+    pub fn force(mut self, force: bool) -> Scanner<'a> {
+        self.force = force;
+        self
+    }
+
+    /// Scan every destination, return one plan entry and one report entry per
+    /// config entry.
+    pub fn scan(&self) -> (Plan, Report) {
+        let options = Options {
+            force: self.force,
+            ..Default::default()
+        };
+        let mut report = Report::new(true);
+        let plan = self::plan(self.configs, &options, &mut report);
+        (plan, report)
+    }
+}
+
+/// Scan destinations of `configs` into a plan, adding their reports to `report`.
+pub(crate) fn plan(configs: &[Config], options: &Options, report: &mut Report) -> Plan {
+    // entries sharing a tree share its walk, and its duration
+    let start = Instant::now();
+    let mut entry_reports: Vec<EntryReport> =
+        configs.iter().map(|_| EntryReport::default()).collect();
+    let entries = helper::scan_all(configs, options, &mut entry_reports);
+
+    let mut plan = Plan::new();
+    for (entry, mut entry_report) in entries.into_iter().zip(entry_reports) {
+        plan.add(entry);
+        entry_report.elapsed(start.elapsed());
+        report.entries.push(entry_report);
+    }
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Kind, matcher::Glob};
+    use std::fs;
+
+    #[test]
+    fn scan_without_removing() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("proj/target/debug")).unwrap();
+        fs::create_dir_all(dir.path().join("vendor/target")).unwrap();
+
+        let configs = vec![Config::new(
+            dir.path(),
+            Kind::Folder,
+            vec!["target"],
+            Some(vec!["vendor"]),
+        )];
+        let (plan, report) = Scanner::new(&configs).scan();
+
+        let paths: Vec<_> = plan.items().map(|item| item.path.clone()).collect();
+        assert_eq!(paths, vec![dir.path().join("proj/target")]);
+        assert_eq!(report.entries[0].excluded, vec![dir.path().join("vendor")]);
+        assert!(dir.path().join("proj/target").exists());
+    }
+
+    #[test]
+    fn scan_with_rule() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("proj/target")).unwrap();
+        fs::create_dir_all(dir.path().join("proj/cmake-build-debug")).unwrap();
+        fs::write(dir.path().join("proj/app.log"), "log").unwrap();
+
+        let rule = Glob::new("cmake-build-*").unwrap();
+        let configs = vec![Config::new(dir.path(), Kind::Folder, vec!["target"], None).rule(rule)];
+        let (plan, _) = Scanner::new(&configs).scan();
+
+        let paths: Vec<_> = plan.items().map(|item| item.path.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                dir.path().join("proj/cmake-build-debug"),
+                dir.path().join("proj/target")
+            ]
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use crate::{stats, walker::Walker};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    fs,
+    ops::{Add, AddAssign},
+    path::{Path, PathBuf},
+};
+
+/// Disk space used by an item.
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Clone, Copy)]
+pub struct Size {
+    /// sum of file lengths.
+    pub apparent: u64,
+    /// allocated blocks on disk.
+    pub disk: u64,
+}
+
+impl Size {
+    /// Size of `path` and its content, symbolic links are not followed.
+    pub fn of<P: AsRef<Path>>(path: P) -> Size {
+        self::measure(path.as_ref(), |_, _| {})
+    }
+
+    /// Size of an entry itself, without any content.
+    pub(crate) fn from_metadata(metadata: &fs::Metadata) -> Size {
+        Size {
+            apparent: metadata.len(),
+            disk: self::disk(metadata),
+        }
+    }
+}
+
+/// Size of `path` and its content, measured in a single walk which also hands
+/// every folder of it (including `path`) to `folder`, along with its size.
+pub(crate) fn measure<F: FnMut(PathBuf, Size)>(path: &Path, mut folder: F) -> Size {
+    stats::stat();
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return Size::default();
+    };
+    let size = Size::from_metadata(&metadata);
+    if !metadata.is_dir() {
+        return size;
+    }
+
+    // folders of the current path, with their size so far, a folder is
+    // complete once the walk leaves it
+    let mut open = vec![(path.to_path_buf(), size)];
+    let mut close = |open: &mut Vec<(PathBuf, Size)>| {
+        let (path, size) = open.pop()?;
+        if let Some((_, parent)) = open.last_mut() {
+            *parent += size;
+        }
+        folder(path, size);
+        Some(size)
+    };
+    for entry in Walker::new(path).flatten() {
+        while open.len() > entry.depth() {
+            close(&mut open);
+        }
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let size = Size::from_metadata(&metadata);
+        match entry.file_type().is_dir() {
+            true => open.push((entry.into_path(), size)),
+            false => {
+                if let Some((_, parent)) = open.last_mut() {
+                    *parent += size;
+                }
+            }
+        }
+    }
+    while open.len() > 1 {
+        close(&mut open);
+    }
+    close(&mut open).unwrap_or_default()
+}
+
+impl Add for Size {
+    type Output = Size;
+
+    fn add(self, other: Size) -> Size {
+        Size {
+            apparent: self.apparent + other.apparent,
+            disk: self.disk + other.disk,
+        }
+    }
+}
+
+impl AddAssign for Size {
+    fn add_assign(&mut self, other: Size) {
+        *self = *self + other;
+    }
+}
+
+#[cfg(unix)]
+fn disk(metadata: &fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    // `st_blocks` is always in 512-byte units
+    metadata.blocks() * 512
+}
+
+#[cfg(not(unix))]
+fn disk(metadata: &fs::Metadata) -> u64 {
+    metadata.len()
+}
+
+/// Human readable bytes (binary units), e.g. `1.5 GiB`.
+pub struct Bytes(pub u64);
+
+impl Display for Bytes {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        const UNITS: [&str; 6] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
+
+        if self.0 < 1024 {
+            return write!(f, "{} B", self.0);
+        }
+
+        let mut value = self.0 as f64;
+        let mut unit = 0;
+        while value >= 1024.0 && unit < UNITS.len() - 1 {
+            value /= 1024.0;
+            unit += 1;
+        }
+        write!(f, "{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn size_of_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let build = dir.path().join("build");
+        fs::create_dir_all(build.join("debug")).unwrap();
+        fs::write(build.join("app"), vec![0; 3000]).unwrap();
+        fs::write(build.join("debug").join("app"), vec![0; 5000]).unwrap();
+
+        let size = Size::of(&build);
+        assert!(size.apparent >= 8000);
+        assert!(size.disk >= 8000);
+        assert_eq!(Size::of(build.join("app")).apparent, 3000);
+        assert_eq!(Size::of(dir.path().join("missing")), Size::default());
+    }
+
+    #[test]
+    fn measure_folders() {
+        let dir = tempfile::tempdir().unwrap();
+        let build = dir.path().join("build");
+        fs::create_dir_all(build.join("debug").join("deps")).unwrap();
+        fs::create_dir_all(build.join("release")).unwrap();
+        fs::write(build.join("debug").join("app"), vec![0; 5000]).unwrap();
+        fs::write(build.join("debug").join("deps").join("lib"), vec![0; 3000]).unwrap();
+
+        // every folder is handed over once, with its whole content
+        let mut folders = HashMap::new();
+        let size = measure(&build, |path, size| {
+            assert!(folders.insert(path, size).is_none());
+        });
+        assert_eq!(folders.len(), 4);
+        assert_eq!(folders[&build], size);
+        let deps = folders[&build.join("debug").join("deps")];
+        let debug = folders[&build.join("debug")];
+        assert!(deps.apparent >= 3000);
+        assert!(debug.apparent >= deps.apparent + 5000);
+        assert!(size.apparent >= debug.apparent + folders[&build.join("release")].apparent);
+    }
+
+    #[test]
+    fn deep_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut path = dir.path().join("build");
+        for _ in 0..500 {
+            path.push("d");
+        }
+        fs::create_dir_all(&path).unwrap();
+        fs::write(path.join("app"), vec![0; 3000]).unwrap();
+
+        // deeper than a recursive walk could go on a small stack
+        let build = dir.path().join("build");
+        let size = std::thread::Builder::new()
+            .stack_size(64 * 1024)
+            .spawn(move || Size::of(build))
+            .unwrap()
+            .join()
+            .unwrap();
+        assert!(size.apparent >= 3000);
+    }
+
+    #[test]
+    fn human_readable() {
+        assert_eq!(Bytes(0).to_string(), "0 B");
+        assert_eq!(Bytes(1023).to_string(), "1023 B");
+        assert_eq!(Bytes(1536).to_string(), "1.5 KiB");
+        assert_eq!(Bytes(3 * 1024 * 1024 * 1024).to_string(), "3.0 GiB");
+    }
+}
@@ -0,0 +1,24 @@
+//! Count of `stat` family calls, for benchmarks.
+//!
+//! Calls are only counted with the `stats` feature, otherwise counting them
+//! does nothing.
+
+#[cfg(feature = "stats")]
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[cfg(feature = "stats")]
+static STATS: AtomicU64 = AtomicU64::new(0);
+
+/// Number of times this process asked the file system about an entry (`stat`
+/// family calls), e.g. to follow a symbolic link or to tell its device. Types
+/// read along with a directory aren't counted.
+#[cfg(feature = "stats")]
+pub fn stats() -> u64 {
+    STATS.load(Ordering::Relaxed)
+}
+
+/// Count a `stat` family call.
+pub(crate) fn stat() {
+    #[cfg(feature = "stats")]
+    STATS.fetch_add(1, Ordering::Relaxed);
+}
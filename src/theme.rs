@@ -0,0 +1,108 @@
+//! Styling of human readable output, shared by messages and errors.
+//!
+//! Styles are only applied when colors are enabled, so piped output stays
+//! free of escape codes.
+
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::{
+    env,
+    fmt::Display,
+    io::{self, IsTerminal},
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+/// When to use colors.
+#[derive(Deserialize, Default, Debug, PartialEq, Clone, Copy, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Color {
+    /// use colors when writing to a terminal and `NO_COLOR` isn't set.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Style {
+    Bold,
+    Underline,
+    /// headings, e.g. `Usage:`.
+    Heading,
+    /// `error:` prefix.
+    Error,
+    /// excluded or skipped items.
+    Warning,
+    /// item about to be removed.
+    Removing,
+    /// removed item.
+    Removed,
+}
+
+const UNSET: u8 = 0;
+const ENABLED: u8 = 1;
+const DISABLED: u8 = 2;
+
+static STATE: AtomicU8 = AtomicU8::new(UNSET);
+
+/// Decide whether to use colors, `stderr_only` tells whether human readable
+/// output is written to stderr only.
+pub fn init(color: Color, stderr_only: bool) {
+    let enabled = match color {
+        Color::Always => true,
+        Color::Never => false,
+        Color::Auto => self::detect(stderr_only),
+    };
+    STATE.store(if enabled { ENABLED } else { DISABLED }, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    match STATE.load(Ordering::Relaxed) {
+        ENABLED => true,
+        DISABLED => false,
+        _ => self::detect(false),
+    }
+}
+
+pub fn paint<T: Display>(style: Style, text: T) -> String {
+    if !self::enabled() {
+        return text.to_string();
+    }
+
+    let code = match style {
+        Style::Bold => "1",
+        Style::Underline => "4",
+        Style::Heading => "1;4",
+        Style::Error => "1;31",
+        Style::Warning => "33",
+        Style::Removing => "91",
+        Style::Removed => "31",
+    };
+    format!("\u{1b}[{}m{}\u{1b}[0m", code, text)
+}
+
+// https://no-color.org
+fn detect(stderr_only: bool) -> bool {
+    let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    let terminal = io::stderr().is_terminal() && (stderr_only || io::stdout().is_terminal());
+    !no_color && terminal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paint_style() {
+        init(Color::Always, false);
+        assert_eq!(
+            paint(Style::Removed, "Removed"),
+            "\u{1b}[31mRemoved\u{1b}[0m"
+        );
+        assert_eq!(paint(Style::Error, "error:"), "\u{1b}[1;31merror:\u{1b}[0m");
+
+        init(Color::Never, false);
+        assert_eq!(paint(Style::Removed, "Removed"), "Removed");
+        assert!(!enabled());
+    }
+}
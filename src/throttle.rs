@@ -0,0 +1,219 @@
+//! Low-priority mode, to keep a workstation responsive during a run.
+//!
+//! `--nice` lowers CPU and I/O priority of the process, `--rate-limit` caps
+//! removals to a number of entries or bytes per second, across all threads.
+
+use serde::Deserialize;
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    io,
+    str::FromStr,
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+static NICE: AtomicBool = AtomicBool::new(false);
+static LIMITED: AtomicBool = AtomicBool::new(false);
+static LIMITER: Mutex<Option<Limiter>> = Mutex::new(None);
+
+/// Maximum pace of removals.
+#[derive(Deserialize, Debug, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Rate {
+    /// files, links and folders removed per second.
+    Entries(u64),
+    /// bytes (on disk) removed per second.
+    Bytes(u64),
+}
+
+/// Spreads removals of every thread over time.
+#[derive(Debug)]
+pub struct Limiter {
+    rate: Rate,
+    /// when the next removal may start.
+    next: Instant,
+}
+
+/// Lower CPU and I/O priority of the process, threads started afterwards
+/// inherit it.
+#[cfg(unix)]
+pub fn nice() -> io::Result<()> {
+    // lowest priority, some systems count it per thread, others per process
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, 19) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    // best-effort class, lowest level, like `ionice -c2 -n7`
+    #[cfg(target_os = "linux")]
+    {
+        const IOPRIO_WHO_PROCESS: libc::c_long = 1;
+        const IOPRIO_CLASS_BE: libc::c_long = 2;
+        const IOPRIO_CLASS_SHIFT: libc::c_long = 13;
+        let ioprio = (IOPRIO_CLASS_BE << IOPRIO_CLASS_SHIFT) | 7;
+        if unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, ioprio) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+
+    NICE.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+// priorities are not available, the run goes on as usual.
+#[cfg(not(unix))]
+pub fn nice() -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "lowering priority is supported on Unix-like systems only",
+    ))
+}
+
+/// Whether priority was lowered.
+pub fn is_nice() -> bool {
+    NICE.load(Ordering::Relaxed)
+}
+
+/// Limit removals of the process to `rate`.
+pub fn limit(rate: Option<Rate>) {
+    if let Ok(mut limiter) = LIMITER.lock() {
+        *limiter = rate.map(Limiter::new);
+        LIMITED.store(rate.is_some(), Ordering::Relaxed);
+    }
+}
+
+/// Rate removals are limited to, if any.
+pub fn rate() -> Option<Rate> {
+    LIMITER.lock().ok()?.as_ref().map(|limiter| limiter.rate)
+}
+
+/// Account removal of `entries` freeing `bytes`, waiting for their turn.
+pub fn take(entries: u64, bytes: u64) {
+    if !LIMITED.load(Ordering::Relaxed) {
+        return;
+    }
+
+    let delay = match LIMITER.lock() {
+        Ok(mut limiter) => limiter
+            .as_mut()
+            .map(|limiter| limiter.take(entries, bytes, Instant::now()))
+            .unwrap_or_default(),
+        Err(_) => Duration::ZERO,
+    };
+    // sleep without holding the lock, other threads take their turn meanwhile
+    if !delay.is_zero() {
+        thread::sleep(delay);
+    }
+}
+
+impl Limiter {
+    pub fn new(rate: Rate) -> Limiter {
+        Limiter {
+            rate,
+            next: Instant::now(),
+        }
+    }
+
+    /// Book removal of `entries` freeing `bytes` at `now`, return how long to
+    /// wait before removing them.
+    pub fn take(&mut self, entries: u64, bytes: u64, now: Instant) -> Duration {
+        let (units, rate) = match self.rate {
+            Rate::Entries(rate) => (entries, rate),
+            Rate::Bytes(rate) => (bytes, rate),
+        };
+        let start = self.next.max(now);
+        self.next = start + Duration::from_secs_f64(units as f64 / rate as f64);
+        start - now
+    }
+}
+
+impl FromStr for Rate {
+    type Err = String;
+
+    /// Number of entries, e.g. `500`, or of bytes with a unit, e.g. `20MiB`.
+    fn from_str(value: &str) -> Result<Rate, String> {
+        const UNITS: [(&str, u64); 4] = [
+            ("k", 1024),
+            ("m", 1024 * 1024),
+            ("g", 1024 * 1024 * 1024),
+            ("", 1),
+        ];
+
+        let value = value.trim().to_lowercase();
+        let number_end = value
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(value.len());
+        let (number, unit) = value.split_at(number_end);
+        let number: u64 = number
+            .parse()
+            .map_err(|_| format!("invalid rate {:?}, e.g. 500 or 20MiB", value))?;
+        if number == 0 {
+            return Err(String::from("rate must be greater than 0"));
+        }
+        if unit.is_empty() {
+            return Ok(Rate::Entries(number));
+        }
+
+        // `k`, `kb` and `kib` are all binary units
+        let prefix = unit.trim_end_matches('b').trim_end_matches('i');
+        UNITS
+            .iter()
+            .find(|(name, _)| *name == prefix)
+            .map(|(_, multiplier)| Rate::Bytes(number.saturating_mul(*multiplier)))
+            .ok_or(format!(
+                "invalid rate unit {:?}, use B, KiB, MiB or GiB",
+                unit
+            ))
+    }
+}
+
+impl Display for Rate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Rate::Entries(rate) => write!(f, "{}", rate),
+            Rate::Bytes(rate) => write!(f, "{}B", rate),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_rate() {
+        assert_eq!("500".parse(), Ok(Rate::Entries(500)));
+        assert_eq!("20MiB".parse(), Ok(Rate::Bytes(20 * 1024 * 1024)));
+        assert_eq!("512k".parse(), Ok(Rate::Bytes(512 * 1024)));
+        assert_eq!("1GB".parse(), Ok(Rate::Bytes(1024 * 1024 * 1024)));
+        assert_eq!("100b".parse(), Ok(Rate::Bytes(100)));
+        assert!("0".parse::<Rate>().is_err());
+        assert!("fast".parse::<Rate>().is_err());
+        assert!("10 parsecs".parse::<Rate>().is_err());
+
+        // round trip, to pass it to another process
+        let rate = Rate::Bytes(2048);
+        assert_eq!(rate.to_string().parse(), Ok(rate));
+    }
+
+    #[test]
+    fn spread_removals() {
+        let now = Instant::now();
+        let mut limiter = Limiter::new(Rate::Entries(10));
+        limiter.next = now;
+        assert_eq!(limiter.take(1, 0, now), Duration::ZERO);
+        assert_eq!(limiter.take(1, 0, now), Duration::from_millis(100));
+        assert_eq!(limiter.take(5, 0, now), Duration::from_millis(200));
+        // time caught up with the booked removals
+        let later = now + Duration::from_secs(1);
+        assert_eq!(limiter.take(1, 0, later), Duration::ZERO);
+
+        let mut limiter = Limiter::new(Rate::Bytes(1024));
+        limiter.next = now;
+        assert_eq!(limiter.take(3, 512, now), Duration::ZERO);
+        assert_eq!(limiter.take(1, 0, now), Duration::from_millis(500));
+    }
+}
@@ -0,0 +1,374 @@
+//! Iterative directory traversal.
+//!
+//! Directories are walked with an explicit stack instead of recursion, so a
+//! pathologically deep tree (e.g. nested `node_modules`) can't overflow the
+//! call stack.
+
+use crate::stats;
+use std::{
+    error::Error,
+    fmt::{Display, Formatter, Result as FmtResult},
+    fs::{self, FileType},
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Depth-first iterator over the content of a directory.
+///
+/// Children of a directory are visited in order of their path, so every walk
+/// of a tree yields the same sequence. Symbolic links are yielded but never
+/// followed, only the root itself may be a symbolic link.
+///
+/// ```no_run
+/// use neaten::Walker;
+///
+/// let mut walker = Walker::new("/usr/sample/rust").max_depth(3);
+/// while let Some(entry) = walker.next() {
+///     let entry = entry?;
+///     if entry.file_name() == "target" {
+///         println!("{}", entry.path().display());
+///         // don't search inside a build folder
+///         walker.skip_current_dir();
+///     }
+/// }
+/// # Ok::<(), neaten::WalkError>(())
+/// ```
+#[derive(Debug)]
+pub struct Walker {
+    root: PathBuf,
+    min_depth: usize,
+    max_depth: usize,
+    /// entries left to visit, per level of the current path, in reverse order.
+    stack: Vec<Vec<WalkEntry>>,
+    /// last yielded directory, read on the next call unless it's skipped.
+    descend: Option<(PathBuf, usize)>,
+    /// errors of the last read directory, yielded before its entries.
+    errors: Vec<WalkError>,
+    /// entries of the next directory to descend into, instead of reading it.
+    listing: Option<Vec<WalkEntry>>,
+    started: bool,
+}
+
+/// Entry of a walk, with its type as read from its directory.
+#[derive(Debug, Clone)]
+pub struct WalkEntry {
+    path: PathBuf,
+    depth: usize,
+    file_type: FileType,
+}
+
+/// Directory, or directory entry, which couldn't be read.
+#[derive(Debug)]
+pub struct WalkError {
+    path: PathBuf,
+    depth: usize,
+    error: io::Error,
+}
+
+impl Walker {
+    /// Walk content of `root`, its children have depth 1.
+    pub fn new<P: Into<PathBuf>>(root: P) -> Walker {
+        Walker {
+            root: root.into(),
+            min_depth: 1,
+            max_depth: usize::MAX,
+            stack: vec![],
+            descend: None,
+            errors: vec![],
+            listing: None,
+            started: false,
+        }
+    }
+
+    /// Don't yield entries above `depth`, they are still walked through.
+    pub fn min_depth(mut self, depth: usize) -> Walker {
+        self.min_depth = depth;
+        self
+    }
+
+    /// Don't walk below `depth`.
+    pub fn max_depth(mut self, depth: usize) -> Walker {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Don't descend into the directory which was yielded last.
+    pub fn skip_current_dir(&mut self) {
+        self.descend = None;
+        self.listing = None;
+    }
+
+    /// Walk `entries` as content of the directory which was yielded last (or
+    /// of the root before the walk starts), instead of reading it.
+    pub(crate) fn listing(&mut self, entries: Vec<WalkEntry>) {
+        self.listing = Some(entries);
+    }
+
+    fn read(&mut self, dir: PathBuf, depth: usize) {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(error) => {
+                self.errors.push(WalkError {
+                    path: dir,
+                    depth,
+                    error,
+                });
+                return;
+            }
+        };
+
+        let mut children = vec![];
+        for entry in entries {
+            // type comes with the entry, no need to ask for it
+            match entry.and_then(|entry| Ok((entry.path(), entry.file_type()?))) {
+                Ok((path, file_type)) => children.push(WalkEntry {
+                    path,
+                    depth,
+                    file_type,
+                }),
+                Err(error) => self.errors.push(WalkError {
+                    path: dir.clone(),
+                    depth,
+                    error,
+                }),
+            }
+        }
+
+        // reverse order, to pop them in order
+        children.sort_by(|a, b| b.path.cmp(&a.path));
+        self.stack.push(children);
+    }
+}
+
+impl Iterator for Walker {
+    type Item = Result<WalkEntry, WalkError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.started {
+            self.started = true;
+            self.descend = Some((self.root.clone(), 0));
+        }
+
+        loop {
+            if let Some((dir, depth)) = self.descend.take()
+                && depth < self.max_depth
+            {
+                match self.listing.take() {
+                    Some(mut entries) => {
+                        entries.iter_mut().for_each(|entry| entry.depth = depth + 1);
+                        entries.sort_by(|a, b| b.path.cmp(&a.path));
+                        self.stack.push(entries);
+                    }
+                    None => self.read(dir, depth + 1),
+                }
+            }
+            if let Some(error) = self.errors.pop() {
+                return Some(Err(error));
+            }
+
+            let level = self.stack.last_mut()?;
+            let Some(entry) = level.pop() else {
+                self.stack.pop();
+                continue;
+            };
+
+            // never descend into a symbolic link
+            if entry.file_type.is_dir() {
+                self.descend = Some((entry.path.clone(), entry.depth));
+            }
+            if entry.depth >= self.min_depth {
+                return Some(Ok(entry));
+            }
+        }
+    }
+}
+
+impl WalkEntry {
+    /// Entry at `path`, its type is asked to the file system.
+    pub(crate) fn from_path<P: Into<PathBuf>>(path: P, depth: usize) -> io::Result<WalkEntry> {
+        let path = path.into();
+        stats::stat();
+        let file_type = fs::symlink_metadata(&path)?.file_type();
+        Ok(WalkEntry {
+            path,
+            depth,
+            file_type,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn into_path(self) -> PathBuf {
+        self.path
+    }
+
+    /// Last component of the path.
+    pub fn file_name(&self) -> &std::ffi::OsStr {
+        self.path.file_name().unwrap_or_default()
+    }
+
+    /// Depth below the root of the walk, its children have depth 1.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Type of the entry itself, a symbolic link isn't followed.
+    pub fn file_type(&self) -> FileType {
+        self.file_type
+    }
+
+    /// Metadata of the entry itself, a symbolic link isn't followed.
+    pub fn metadata(&self) -> io::Result<fs::Metadata> {
+        stats::stat();
+        fs::symlink_metadata(&self.path)
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.file_type.is_symlink()
+    }
+
+    /// Whether it's a directory, following a symbolic link like `Path::is_dir`.
+    pub fn is_dir(&self) -> bool {
+        if self.is_symlink() {
+            stats::stat();
+            self.path.is_dir()
+        } else {
+            self.file_type.is_dir()
+        }
+    }
+
+    /// Whether it's a file, following a symbolic link like `Path::is_file`.
+    pub fn is_file(&self) -> bool {
+        if self.is_symlink() {
+            stats::stat();
+            self.path.is_file()
+        } else {
+            self.file_type.is_file()
+        }
+    }
+}
+
+impl WalkError {
+    /// Directory which couldn't be read.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Depth of the entries which couldn't be read.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+}
+
+impl Display for WalkError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "reading directory {:?}: {}", self.path, self.error)
+    }
+}
+
+impl Error for WalkError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("b").join("target").join("debug")).unwrap();
+        fs::create_dir_all(dir.path().join("a").join("src")).unwrap();
+        fs::write(dir.path().join("a").join("src").join("main.rs"), "").unwrap();
+        fs::write(dir.path().join("c.txt"), "").unwrap();
+        dir
+    }
+
+    fn relative(dir: &Path, walker: Walker) -> Vec<(String, usize)> {
+        walker
+            .map(|entry| {
+                let entry = entry.unwrap();
+                let path = entry.path().strip_prefix(dir).unwrap();
+                (path.to_str().unwrap().replace('\\', "/"), entry.depth())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn walk_in_order() {
+        let dir = tree();
+        assert_eq!(
+            relative(dir.path(), Walker::new(dir.path())),
+            vec![
+                (String::from("a"), 1),
+                (String::from("a/src"), 2),
+                (String::from("a/src/main.rs"), 3),
+                (String::from("b"), 1),
+                (String::from("b/target"), 2),
+                (String::from("b/target/debug"), 3),
+                (String::from("c.txt"), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn walk_depth() {
+        let dir = tree();
+        let walker = Walker::new(dir.path()).min_depth(2).max_depth(2);
+        assert_eq!(
+            relative(dir.path(), walker),
+            vec![(String::from("a/src"), 2), (String::from("b/target"), 2)]
+        );
+    }
+
+    #[test]
+    fn skip_current_dir() {
+        let dir = tree();
+        let mut walker = Walker::new(dir.path());
+        let mut paths = vec![];
+        while let Some(entry) = walker.next() {
+            let entry = entry.unwrap();
+            if entry.file_name() == "target" {
+                walker.skip_current_dir();
+            }
+            paths.push(entry.into_path());
+        }
+        assert!(paths.contains(&dir.path().join("b").join("target")));
+        assert!(!paths.contains(&dir.path().join("b").join("target").join("debug")));
+    }
+
+    #[test]
+    fn deep_tree() {
+        // deeper than a recursive walk could go on a small stack
+        let dir = tempfile::tempdir().unwrap();
+        let mut path = dir.path().to_path_buf();
+        for _ in 0..1000 {
+            path.push("d");
+        }
+        fs::create_dir_all(&path).unwrap();
+
+        let count = std::thread::Builder::new()
+            .stack_size(64 * 1024)
+            .spawn({
+                let root = dir.path().to_path_buf();
+                move || Walker::new(root).count()
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+        assert_eq!(count, 1000);
+    }
+
+    #[test]
+    fn missing_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let errors: Vec<WalkError> = Walker::new(dir.path().join("missing"))
+            .filter_map(Result::err)
+            .collect();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].depth(), 1);
+    }
+}
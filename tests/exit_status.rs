@@ -0,0 +1,146 @@
+//! Exit status of the binary, so that scripts and cron jobs can tell a failed
+//! run from a successful one.
+
+use std::{
+    fs::{self, File},
+    path::Path,
+    process::{Command, Output},
+};
+
+fn neaten(state: &Path, args: &[&str]) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_neaten"));
+    command
+        .env("XDG_STATE_HOME", state)
+        .env("XDG_CACHE_HOME", state)
+        .env("XDG_RUNTIME_DIR", state.join("runtime"))
+        .args(args);
+    command
+}
+
+fn run(state: &Path, args: &[&str]) -> Output {
+    neaten(state, args).output().unwrap()
+}
+
+#[test]
+fn success() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("tree/proj/target")).unwrap();
+    let tree = dir.path().join("tree");
+
+    let output = run(
+        dir.path(),
+        &["-d", tree.to_str().unwrap(), "-k", "folder", "-p", "target"],
+    );
+    assert_eq!(output.status.code(), Some(0));
+    assert!(!tree.join("proj/target").exists());
+}
+
+#[test]
+fn missing_destination() {
+    let dir = tempfile::tempdir().unwrap();
+    let missing = dir.path().join("missing");
+
+    let output = run(
+        dir.path(),
+        &[
+            "-d",
+            missing.to_str().unwrap(),
+            "-k",
+            "folder",
+            "-p",
+            "target",
+        ],
+    );
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn locked_destination() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("tree/proj/target")).unwrap();
+    let tree = dir.path().join("tree");
+
+    // a first run leaves its lock files, hold them as another run would
+    let args = [
+        "-d",
+        tree.to_str().unwrap(),
+        "-k",
+        "folder",
+        "-p",
+        "nothing",
+    ];
+    assert_eq!(run(dir.path(), &args).status.code(), Some(0));
+    let locks: Vec<File> = fs::read_dir(dir.path().join("runtime/neaten"))
+        .unwrap()
+        .map(|entry| {
+            File::options()
+                .write(true)
+                .open(entry.unwrap().path())
+                .unwrap()
+        })
+        .collect();
+    locks.iter().for_each(|lock| lock.lock().unwrap());
+
+    let args = ["-d", tree.to_str().unwrap(), "-k", "folder", "-p", "target"];
+    let output = run(dir.path(), &args);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("locked by another neaten run"));
+    assert!(tree.join("proj/target").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn interrupted() {
+    let dir = tempfile::tempdir().unwrap();
+    for project in 0..4 {
+        let target = dir.path().join(format!("tree/proj{}/target", project));
+        fs::create_dir_all(&target).unwrap();
+        for file in 0..10 {
+            fs::write(target.join(format!("file{}.o", file)), "object").unwrap();
+        }
+    }
+    let tree = dir.path().join("tree");
+
+    // slow enough to be interrupted while removing
+    let mut child = neaten(
+        dir.path(),
+        &[
+            "-d",
+            tree.to_str().unwrap(),
+            "-k",
+            "folder",
+            "-p",
+            "target",
+            "--rate-limit",
+            "5",
+            "--jobs",
+            "1",
+        ],
+    )
+    .spawn()
+    .unwrap();
+    std::thread::sleep(std::time::Duration::from_secs(2));
+    unsafe { libc::kill(child.id() as libc::pid_t, libc::SIGINT) };
+
+    assert_eq!(child.wait().unwrap().code(), Some(130));
+    // items which weren't started are left to resume
+    assert!(tree.join("proj3/target").exists());
+
+    // only by a run of the same destination
+    let other = dir.path().join("other");
+    fs::create_dir(&other).unwrap();
+    let args = ["-k", "folder", "-p", "target"];
+    let output = run(
+        dir.path(),
+        &[&["resume", "-d", other.to_str().unwrap()], &args[..]].concat(),
+    );
+    assert_eq!(output.status.code(), Some(1));
+    assert!(tree.join("proj3/target").exists());
+
+    let output = run(
+        dir.path(),
+        &[&["resume", "-d", tree.to_str().unwrap()], &args[..]].concat(),
+    );
+    assert_eq!(output.status.code(), Some(0));
+    assert!(!tree.join("proj3/target").exists());
+}
@@ -0,0 +1,50 @@
+//! Trees beyond the limits of the system, e.g. deeper than the number of files
+//! a process may open.
+
+#![cfg(unix)]
+
+use std::{fs, os::unix::process::CommandExt, process::Command};
+
+/// Descriptors the run may open, far fewer than levels of the tree.
+const OPEN_FILES: libc::rlim_t = 64;
+
+#[test]
+fn deeper_than_open_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let target = dir.path().join("tree/proj/target");
+    let mut path = target.clone();
+    for _ in 0..4 * OPEN_FILES {
+        path.push("d");
+    }
+    fs::create_dir_all(&path).unwrap();
+    fs::write(path.join("app"), "binary").unwrap();
+
+    let mut command = Command::new(env!("CARGO_BIN_EXE_neaten"));
+    command
+        .env("XDG_STATE_HOME", dir.path())
+        .env("XDG_CACHE_HOME", dir.path())
+        .env("XDG_RUNTIME_DIR", dir.path().join("runtime"))
+        .args(["-d", dir.path().join("tree").to_str().unwrap()])
+        .args(["-k", "folder", "-p", "target"]);
+    unsafe {
+        command.pre_exec(|| {
+            let limit = libc::rlimit {
+                rlim_cur: OPEN_FILES,
+                rlim_max: OPEN_FILES,
+            };
+            match libc::setrlimit(libc::RLIMIT_NOFILE, &limit) {
+                0 => Ok(()),
+                _ => Err(std::io::Error::last_os_error()),
+            }
+        });
+    }
+
+    let output = command.output().unwrap();
+    assert_eq!(
+        output.status.code(),
+        Some(0),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(!target.exists());
+}